@@ -0,0 +1,69 @@
+//! Decodes an SPI capture exported from a logic analyser as CSV and prints
+//! one line per command.
+//!
+//! The last two columns of each line are taken as the level of DC and the
+//! byte sent, e.g. `0.000125,0,0x01`. DC may be `0`/`1` or `low`/`high`; the
+//! byte may be decimal or `0x` prefixed hex. Lines that do not parse, such as
+//! headers, are skipped.
+//!
+//! Usage: cargo run --example decode_capture -- capture.csv
+
+extern crate cfap_screen;
+
+use std::env;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::process;
+
+use cfap_screen::decode::decode;
+
+fn parse_dc(field: &str) -> Option<bool> {
+    match field.trim().to_ascii_lowercase().as_str() {
+        "0" | "low" | "false" => Some(false),
+        "1" | "high" | "true" => Some(true),
+        _ => None,
+    }
+}
+
+fn parse_byte(field: &str) -> Option<u8> {
+    let field = field.trim();
+    if field.starts_with("0x") || field.starts_with("0X") {
+        u8::from_str_radix(&field[2..], 16).ok()
+    } else {
+        field.parse().ok()
+    }
+}
+
+fn parse_line(line: &str) -> Option<(bool, u8)> {
+    let mut fields = line.rsplit(',');
+    let byte = parse_byte(fields.next()?)?;
+    let dc = parse_dc(fields.next()?)?;
+    Some((dc, byte))
+}
+
+fn main() {
+    let path = match env::args().nth(1) {
+        Some(path) => path,
+        None => {
+            eprintln!("usage: decode_capture <capture.csv>");
+            process::exit(2);
+        }
+    };
+
+    let file = File::open(&path).unwrap_or_else(|err| {
+        eprintln!("{}: {}", path, err);
+        process::exit(1);
+    });
+
+    let lines: Vec<String> = BufReader::new(file)
+        .lines()
+        .collect::<io::Result<_>>()
+        .unwrap_or_else(|err| {
+            eprintln!("{}: {}", path, err);
+            process::exit(1);
+        });
+
+    for event in decode(lines.iter().filter_map(|line| parse_line(line))) {
+        println!("{}", event);
+    }
+}
@@ -0,0 +1,218 @@
+//! Decoders for the image formats read by `include_panel_image!`.
+
+/// An 8 bit grayscale image, 0 is black.
+pub struct Gray {
+    pub width: usize,
+    pub height: usize,
+    pub luma: Vec<u8>,
+}
+
+impl Gray {
+    pub fn get(&self, x: usize, y: usize) -> u8 {
+        self.luma[y * self.width + x]
+    }
+}
+
+/// Decodes a PBM (P1, P4), PGM (P2, P5), or uncompressed BMP image.
+pub fn decode(data: &[u8]) -> Result<Gray, String> {
+    match data.get(..2) {
+        Some(b"P1") | Some(b"P2") | Some(b"P4") | Some(b"P5") => decode_pnm(data),
+        Some(b"BM") => decode_bmp(data),
+        _ => Err("unknown image format, expected PBM, PGM, or BMP".into()),
+    }
+}
+
+struct Header<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Header<'a> {
+    fn skip_space(&mut self) {
+        while self.pos < self.data.len() {
+            match self.data[self.pos] {
+                b'#' => {
+                    while self.pos < self.data.len() && self.data[self.pos] != b'\n' {
+                        self.pos += 1;
+                    }
+                }
+                b if b.is_ascii_whitespace() => self.pos += 1,
+                _ => break,
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<usize, String> {
+        self.skip_space();
+        let start = self.pos;
+        while self.pos < self.data.len() && self.data[self.pos].is_ascii_digit() {
+            self.pos += 1;
+        }
+        if start == self.pos {
+            return Err("malformed PNM header".into());
+        }
+        let digits = std::str::from_utf8(&self.data[start..self.pos]).unwrap();
+        digits.parse().map_err(|_| "number out of range in PNM header".into())
+    }
+
+    /// A single digit, as P1 allows pixels without separating whitespace.
+    fn digit(&mut self) -> Result<usize, String> {
+        self.skip_space();
+        match self.data.get(self.pos) {
+            Some(&b) if b.is_ascii_digit() => {
+                self.pos += 1;
+                Ok((b - b'0') as usize)
+            }
+            _ => Err("truncated PNM image".into()),
+        }
+    }
+}
+
+fn decode_pnm(data: &[u8]) -> Result<Gray, String> {
+    let kind = data[1];
+    let mut header = Header { data, pos: 2 };
+    let width = header.number()?;
+    let height = header.number()?;
+    let max = if kind == b'2' || kind == b'5' {
+        header.number()?
+    } else {
+        1
+    };
+    if max == 0 || max > 0xffff {
+        return Err("PGM maximum value must be from 1 to 65535".into());
+    }
+    let scale = |v: usize| -> Result<u8, String> {
+        if v > max {
+            return Err("PGM value above the maximum".into());
+        }
+        Ok((v * 255 / max) as u8)
+    };
+
+    let count = width * height;
+    let mut luma = Vec::with_capacity(count);
+    match kind {
+        b'1' => {
+            for _ in 0..count {
+                // 1 is black
+                luma.push(if header.digit()? == 0 { 0xff } else { 0x00 });
+            }
+        }
+        b'2' => {
+            for _ in 0..count {
+                luma.push(scale(header.number()?)?);
+            }
+        }
+        _ => {
+            // a single whitespace byte separates the header from the raster
+            let raster = data.get(header.pos + 1..).unwrap_or(&[]);
+            if kind == b'4' {
+                let row_bytes = (width + 7) / 8;
+                if raster.len() < row_bytes * height {
+                    return Err("truncated PBM image".into());
+                }
+                for y in 0..height {
+                    for x in 0..width {
+                        let bit = raster[y * row_bytes + x / 8] & (0x80 >> (x % 8));
+                        luma.push(if bit == 0 { 0xff } else { 0x00 });
+                    }
+                }
+            } else {
+                let depth = if max < 256 { 1 } else { 2 };
+                if raster.len() < count * depth {
+                    return Err("truncated PGM image".into());
+                }
+                for i in 0..count {
+                    let v = if depth == 1 {
+                        raster[i] as usize
+                    } else {
+                        (raster[2 * i] as usize) << 8 | raster[2 * i + 1] as usize
+                    };
+                    luma.push(scale(v)?);
+                }
+            }
+        }
+    }
+
+    Ok(Gray { width, height, luma })
+}
+
+fn u16_at(data: &[u8], pos: usize) -> Result<u16, String> {
+    data.get(pos..pos + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+        .ok_or_else(|| "truncated BMP header".into())
+}
+
+fn u32_at(data: &[u8], pos: usize) -> Result<u32, String> {
+    data.get(pos..pos + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or_else(|| "truncated BMP header".into())
+}
+
+fn luma(r: u8, g: u8, b: u8) -> u8 {
+    ((299 * r as u32 + 587 * g as u32 + 114 * b as u32) / 1000) as u8
+}
+
+fn decode_bmp(data: &[u8]) -> Result<Gray, String> {
+    let offset = u32_at(data, 10)? as usize;
+    let dib_size = u32_at(data, 14)? as usize;
+    if dib_size < 40 {
+        return Err("only BMPs with a BITMAPINFOHEADER or later are supported".into());
+    }
+    let width = u32_at(data, 18)? as i32;
+    let height = u32_at(data, 22)? as i32;
+    let bpp = u16_at(data, 28)? as usize;
+    let compression = u32_at(data, 30)?;
+    // 3 is BI_BITFIELDS, accepted for 32 bit images with the usual masks
+    if compression != 0 && !(compression == 3 && bpp == 32) {
+        return Err("compressed BMPs are not supported".into());
+    }
+    if width <= 0 || height == 0 {
+        return Err("BMP has no pixels".into());
+    }
+    let width = width as usize;
+    let top_down = height < 0;
+    let height = height.unsigned_abs() as usize;
+
+    let palette = match bpp {
+        1 | 4 | 8 => {
+            let used = u32_at(data, 46)? as usize;
+            let colors = if used == 0 { 1 << bpp } else { used };
+            let start = 14 + dib_size;
+            let table = data.get(start..start + colors * 4).ok_or("truncated BMP palette")?;
+            table.chunks(4).map(|c| luma(c[2], c[1], c[0])).collect()
+        }
+        24 | 32 => Vec::new(),
+        _ => return Err(format!("{} bit BMPs are not supported", bpp)),
+    };
+
+    let stride = (bpp * width + 31) / 32 * 4;
+    if data.len() < offset + stride * height {
+        return Err("truncated BMP image".into());
+    }
+
+    let mut luma_data = Vec::with_capacity(width * height);
+    for y in 0..height {
+        let row_y = if top_down { y } else { height - 1 - y };
+        let row = &data[offset + row_y * stride..offset + (row_y + 1) * stride];
+        for x in 0..width {
+            let value = match bpp {
+                24 | 32 => {
+                    let p = &row[x * bpp / 8..];
+                    luma(p[2], p[1], p[0])
+                }
+                _ => {
+                    let bit = x * bpp;
+                    let index = (row[bit / 8] >> (8 - bpp - bit % 8)) & ((1 << bpp) - 1) as u8;
+                    *palette.get(index as usize).ok_or("BMP palette index out of range")?
+                }
+            };
+            luma_data.push(value);
+        }
+    }
+
+    Ok(Gray {
+        width,
+        height,
+        luma: luma_data,
+    })
+}
@@ -0,0 +1,192 @@
+//! Compile time image conversion for `cfap_screen`, re-exported from there
+//! with the `macros` feature.
+
+extern crate proc_macro;
+
+mod image;
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use proc_macro::{Delimiter, TokenStream, TokenTree};
+
+use image::Gray;
+
+/// Reads a PBM, PGM, or BMP file and expands to a `[u8; N]` packed the same
+/// as images given to `Screen::load_image`: rows of
+/// `width_pixels_to_bytes(width)` bytes, leftmost pixel in the MSB, a set bit
+/// white, and unused bits of the last byte of a row set.
+///
+/// ```ignore
+/// static LOGO: [u8; 5000] = include_panel_image!("logo.pbm", threshold = 100, rotation = 90);
+/// ```
+///
+/// The path is relative to the directory of the crate's `Cargo.toml`. The
+/// optional arguments are:
+///
+/// - `threshold`: gray levels from 0 to 255 at or above this are white.
+///   Defaults to 128.
+/// - `rotation`: degrees to rotate the image clockwise, 0, 90, 180, or 270.
+///   Defaults to 0.
+/// - `width`, `height`: the panel size in pixels. The rotated image is placed
+///   in the top left and the rest filled with white. Defaults to the size of
+///   the rotated image.
+#[proc_macro]
+pub fn include_panel_image(input: TokenStream) -> TokenStream {
+    match expand(input) {
+        Ok(tokens) => tokens,
+        Err(msg) => format!("compile_error!({:?})", msg).parse().unwrap(),
+    }
+}
+
+struct Args {
+    path: String,
+    threshold: u8,
+    rotation: u16,
+    width: Option<usize>,
+    height: Option<usize>,
+}
+
+fn expand(input: TokenStream) -> Result<TokenStream, String> {
+    let args = parse_args(input)?;
+
+    let root = env::var("CARGO_MANIFEST_DIR").map_err(|_| "CARGO_MANIFEST_DIR is not set")?;
+    let path = PathBuf::from(root).join(&args.path);
+    let data = fs::read(&path).map_err(|err| format!("{}: {}", path.display(), err))?;
+    let image = image::decode(&data).map_err(|err| format!("{}: {}", path.display(), err))?;
+
+    let bytes = pack(&image, &args).map_err(|err| format!("{}: {}", path.display(), err))?;
+
+    let path = path.to_str().ok_or("image path is not valid UTF-8")?;
+    let mut out = String::new();
+    // include_bytes! so the image is rebuilt when the file changes
+    out.push_str(&format!("{{ const _: &[u8] = include_bytes!({:?}); [", path));
+    for b in bytes {
+        out.push_str(&format!("{:#04x}u8,", b));
+    }
+    out.push_str("] }");
+
+    Ok(out.parse().unwrap())
+}
+
+fn parse_args(input: TokenStream) -> Result<Args, String> {
+    let mut tokens = input.into_iter();
+
+    let path = match tokens.next() {
+        Some(TokenTree::Literal(lit)) => parse_str(&lit.to_string())?,
+        // macro_rules! wrappers pass arguments in an invisible group
+        Some(TokenTree::Group(ref group)) if group.delimiter() == Delimiter::None => {
+            match group.stream().into_iter().next() {
+                Some(TokenTree::Literal(lit)) => parse_str(&lit.to_string())?,
+                _ => return Err("expected the image path as a string literal".into()),
+            }
+        }
+        _ => return Err("expected the image path as a string literal".into()),
+    };
+
+    let mut args = Args {
+        path,
+        threshold: 128,
+        rotation: 0,
+        width: None,
+        height: None,
+    };
+
+    loop {
+        match tokens.next() {
+            None => break,
+            Some(TokenTree::Punct(ref p)) if p.as_char() == ',' => {}
+            _ => return Err("expected `,` between arguments".into()),
+        }
+        let name = match tokens.next() {
+            None => break,
+            Some(TokenTree::Ident(ident)) => ident.to_string(),
+            _ => return Err("expected an argument name".into()),
+        };
+        match tokens.next() {
+            Some(TokenTree::Punct(ref p)) if p.as_char() == '=' => {}
+            _ => return Err(format!("expected `=` after `{}`", name)),
+        }
+        let value = match tokens.next() {
+            Some(TokenTree::Literal(lit)) => parse_int(&lit.to_string())
+                .ok_or_else(|| format!("`{}` must be an integer", name))?,
+            _ => return Err(format!("`{}` must be an integer", name)),
+        };
+
+        match name.as_str() {
+            "threshold" if value <= 255 => args.threshold = value as u8,
+            "threshold" => return Err("`threshold` must be from 0 to 255".into()),
+            "rotation" if value % 90 == 0 && value < 360 => args.rotation = value as u16,
+            "rotation" => return Err("`rotation` must be 0, 90, 180, or 270".into()),
+            "width" => args.width = Some(value),
+            "height" => args.height = Some(value),
+            _ => return Err(format!("unknown argument `{}`", name)),
+        }
+    }
+
+    Ok(args)
+}
+
+fn parse_str(lit: &str) -> Result<String, String> {
+    if !lit.starts_with('"') || !lit.ends_with('"') || lit.len() < 2 {
+        return Err("expected the image path as a string literal".into());
+    }
+    let mut out = String::new();
+    let mut chars = lit[1..lit.len() - 1].chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('\\') => out.push('\\'),
+                Some('"') => out.push('"'),
+                _ => return Err("unsupported escape in image path".into()),
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    Ok(out)
+}
+
+fn parse_int(lit: &str) -> Option<usize> {
+    let digits: String = lit.chars().take_while(|c| c.is_ascii_digit() || *c == '_').collect();
+    let rest = &lit[digits.len()..];
+    if !(rest.is_empty() || rest.starts_with('u') || rest.starts_with('i')) {
+        return None;
+    }
+    digits.replace('_', "").parse().ok()
+}
+
+/// Rotates, pads, thresholds, and packs the image.
+fn pack(image: &Gray, args: &Args) -> Result<Vec<u8>, String> {
+    let (rotated_width, rotated_height) = match args.rotation {
+        90 | 270 => (image.height, image.width),
+        _ => (image.width, image.height),
+    };
+    let width = args.width.unwrap_or(rotated_width);
+    let height = args.height.unwrap_or(rotated_height);
+    if rotated_width > width || rotated_height > height {
+        return Err(format!(
+            "the image is {}x{} after rotation, larger than the panel, {}x{}",
+            rotated_width, rotated_height, width, height
+        ));
+    }
+
+    let row_bytes = (width + 7) / 8;
+    let mut bytes = vec![0xff; row_bytes * height];
+    for y in 0..rotated_height {
+        for x in 0..rotated_width {
+            let (src_x, src_y) = match args.rotation {
+                90 => (y, image.height - 1 - x),
+                180 => (image.width - 1 - x, image.height - 1 - y),
+                270 => (image.width - 1 - y, x),
+                _ => (x, y),
+            };
+            if image.get(src_x, src_y) < args.threshold {
+                bytes[y * row_bytes + x / 8] &= !(0x80 >> (x % 8));
+            }
+        }
+    }
+
+    Ok(bytes)
+}
@@ -0,0 +1,57 @@
+//! Band rendering, for drawing full screen images with a buffer of only a
+//! few rows.
+
+use super::{width_pixels_to_bytes, Canvas, Color, ImageSource};
+
+/// Renders an image a band of rows at a time, so only a band needs to be
+/// held in memory.
+///
+/// Each band is cleared to white, drawn by calling `draw` with a `Canvas`
+/// over it, then streamed. `draw` is called once per band and should draw
+/// the whole scene; what falls outside the band is ignored.
+pub struct Bands<'a, F> {
+    buf: &'a mut [u8],
+    width: u16,
+    height: u16,
+    draw: F,
+}
+
+impl<'a, F: FnMut(&mut Canvas)> Bands<'a, F> {
+    /// Returns `None` if `buf` cannot hold a single row of `width` pixels.
+    pub fn new(buf: &'a mut [u8], width: u16, height: u16, draw: F) -> Option<Bands<'a, F>> {
+        if buf.len() < width_pixels_to_bytes(width) as usize {
+            return None;
+        }
+
+        Some(Bands {
+            buf,
+            width,
+            height,
+            draw,
+        })
+    }
+}
+
+impl<'a, F: FnMut(&mut Canvas)> ImageSource for Bands<'a, F> {
+    fn len(&self) -> usize {
+        width_pixels_to_bytes(self.width) as usize * self.height as usize
+    }
+
+    fn stream<E, S>(&mut self, mut sink: S) -> Result<(), E>
+    where
+        S: FnMut(u8) -> Result<(), E>,
+    {
+        let mut top = 0;
+        while top < self.height {
+            let mut band = Canvas::new_band(self.buf, self.width, self.height, top);
+            band.fill(Color::White);
+            (self.draw)(&mut band);
+
+            for &b in band.as_bytes() {
+                sink(b)?;
+            }
+            top += band.rows();
+        }
+        Ok(())
+    }
+}
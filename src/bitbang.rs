@@ -0,0 +1,236 @@
+//! SPI driven from GPIO pins, for boards without a free SPI peripheral.
+//!
+//! `BitBangSpi` uses mode 0 (SCK idles low, data is sampled on the rising
+//! edge) and sends MSB first, so it can be used as the `SPI` of a `Screen`:
+//!
+//! ```ignore
+//! let spi = BitBangSpi::new(sck, mosi, NoMiso, delay, 1);
+//! let screen = builder.new_screen(spi, dc, cs, busy, reset, &mut delay)?;
+//! ```
+//!
+//! It also implements `FullDuplex<u16>` for the 9-bit frames of the 3-wire
+//! mode, where the first bit sent takes the place of the DC pin. `ThreeWire`
+//! splits any such SPI into the SPI and DC pin of a `Screen`:
+//!
+//! ```ignore
+//! let three_wire = ThreeWire::new();
+//! let (spi, dc) = three_wire.split(BitBangSpi::new(sck, mosi, NoMiso, delay, 1));
+//! let screen = builder.new_screen(spi, dc, cs, busy, reset, &mut delay)?;
+//! ```
+
+use core::cell::Cell;
+use core::convert::Infallible;
+
+use embedded_hal::blocking::delay::DelayUs;
+#[allow(deprecated)]
+use embedded_hal::digital::{InputPin, OutputPin};
+use embedded_hal::spi::FullDuplex;
+
+/// A MISO that always reads low, for write-only wiring.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoMiso;
+
+#[allow(deprecated)]
+impl InputPin for NoMiso {
+    fn is_high(&self) -> bool {
+        false
+    }
+
+    fn is_low(&self) -> bool {
+        true
+    }
+}
+
+/// Builds a 3-wire frame: the DC bit followed by the byte.
+pub const fn frame9(dc: bool, byte: u8) -> u16 {
+    (dc as u16) << 8 | byte as u16
+}
+
+pub struct BitBangSpi<SCK, MOSI, MISO, DELAY> {
+    sck: SCK,
+    mosi: MOSI,
+    miso: MISO,
+    delay: DELAY,
+    /// Microseconds spent in each half of a clock period.
+    half_period: u16,
+    /// The word received by the last send, taken by the next read.
+    received: Option<u16>,
+}
+
+#[allow(deprecated)]
+impl<SCK, MOSI, MISO, DELAY> BitBangSpi<SCK, MOSI, MISO, DELAY>
+where
+    SCK: OutputPin,
+    MOSI: OutputPin,
+    MISO: InputPin,
+    DELAY: DelayUs<u16>,
+{
+    /// Takes the pins and a delay of `half_period` microseconds per clock
+    /// edge. A `half_period` of 0 clocks as fast as the pins can toggle.
+    ///
+    /// SCK is driven low immediately.
+    pub fn new(
+        mut sck: SCK,
+        mosi: MOSI,
+        miso: MISO,
+        delay: DELAY,
+        half_period: u16,
+    ) -> BitBangSpi<SCK, MOSI, MISO, DELAY> {
+        sck.set_low();
+
+        BitBangSpi {
+            sck,
+            mosi,
+            miso,
+            delay,
+            half_period,
+            received: None,
+        }
+    }
+
+    /// Returns the pins and delay.
+    pub fn free(self) -> (SCK, MOSI, MISO, DELAY) {
+        (self.sck, self.mosi, self.miso, self.delay)
+    }
+
+    /// Shifts out the low `bits` bits of `word`, MSB first, returning the
+    /// bits shifted in.
+    ///
+    /// Panics if `bits` is more than 16.
+    pub fn transfer_bits(&mut self, word: u16, bits: u8) -> u16 {
+        assert!(bits <= 16, "a word has at most 16 bits, not {}", bits);
+
+        let mut received = 0;
+        for bit in (0..bits).rev() {
+            if word & (1 << bit) != 0 {
+                self.mosi.set_high();
+            } else {
+                self.mosi.set_low();
+            }
+            self.wait();
+
+            self.sck.set_high();
+            received = received << 1 | self.miso.is_high() as u16;
+            self.wait();
+
+            self.sck.set_low();
+        }
+        received
+    }
+
+    fn wait(&mut self) {
+        if self.half_period > 0 {
+            self.delay.delay_us(self.half_period);
+        }
+    }
+
+    fn take_received(&mut self) -> nb::Result<u16, Infallible> {
+        self.received.take().ok_or(nb::Error::WouldBlock)
+    }
+}
+
+#[allow(deprecated)]
+impl<SCK, MOSI, MISO, DELAY> FullDuplex<u8> for BitBangSpi<SCK, MOSI, MISO, DELAY>
+where
+    SCK: OutputPin,
+    MOSI: OutputPin,
+    MISO: InputPin,
+    DELAY: DelayUs<u16>,
+{
+    type Error = Infallible;
+
+    fn read(&mut self) -> nb::Result<u8, Infallible> {
+        self.take_received().map(|word| word as u8)
+    }
+
+    fn send(&mut self, byte: u8) -> nb::Result<(), Infallible> {
+        self.received = Some(self.transfer_bits(byte as u16, 8));
+        Ok(())
+    }
+}
+
+/// 9-bit frames for the 3-wire mode. Only the low 9 bits are sent.
+#[allow(deprecated)]
+impl<SCK, MOSI, MISO, DELAY> FullDuplex<u16> for BitBangSpi<SCK, MOSI, MISO, DELAY>
+where
+    SCK: OutputPin,
+    MOSI: OutputPin,
+    MISO: InputPin,
+    DELAY: DelayUs<u16>,
+{
+    type Error = Infallible;
+
+    fn read(&mut self) -> nb::Result<u16, Infallible> {
+        self.take_received()
+    }
+
+    fn send(&mut self, frame: u16) -> nb::Result<(), Infallible> {
+        self.received = Some(self.transfer_bits(frame, 9));
+        Ok(())
+    }
+}
+
+/// The DC bit of the 3-wire mode, shared between the halves returned by
+/// `split`. The `Screen` drives `ThreeWireDc` as its DC pin, and
+/// `ThreeWireSpi` sends the level it was last set to as the first bit of
+/// each 9-bit frame.
+#[derive(Debug, Default)]
+pub struct ThreeWire {
+    dc: Cell<bool>,
+}
+
+impl ThreeWire {
+    pub const fn new() -> ThreeWire {
+        ThreeWire { dc: Cell::new(false) }
+    }
+
+    /// Splits `spi`, which sends 9-bit frames, into the SPI and DC pin of a
+    /// `Screen`.
+    pub fn split<'a, SPI>(&'a self, spi: SPI) -> (ThreeWireSpi<'a, SPI>, ThreeWireDc<'a>) {
+        (ThreeWireSpi { spi, dc: &self.dc }, ThreeWireDc { dc: &self.dc })
+    }
+}
+
+/// Bytes sent as 9-bit frames led by the DC bit. See `ThreeWire`.
+pub struct ThreeWireSpi<'a, SPI> {
+    spi: SPI,
+    dc: &'a Cell<bool>,
+}
+
+impl<'a, SPI> ThreeWireSpi<'a, SPI> {
+    /// Returns the 9-bit SPI.
+    pub fn free(self) -> SPI {
+        self.spi
+    }
+}
+
+impl<'a, SPI> FullDuplex<u8> for ThreeWireSpi<'a, SPI>
+where
+    SPI: FullDuplex<u16>,
+{
+    type Error = SPI::Error;
+
+    fn read(&mut self) -> nb::Result<u8, SPI::Error> {
+        self.spi.read().map(|frame| frame as u8)
+    }
+
+    fn send(&mut self, byte: u8) -> nb::Result<(), SPI::Error> {
+        self.spi.send(frame9(self.dc.get(), byte))
+    }
+}
+
+/// The DC pin of the 3-wire mode. See `ThreeWire`.
+pub struct ThreeWireDc<'a> {
+    dc: &'a Cell<bool>,
+}
+
+#[allow(deprecated)]
+impl<'a> OutputPin for ThreeWireDc<'a> {
+    fn set_low(&mut self) {
+        self.dc.set(false);
+    }
+
+    fn set_high(&mut self) {
+        self.dc.set(true);
+    }
+}
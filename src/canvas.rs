@@ -0,0 +1,186 @@
+//! Drawing on 1 bit per pixel images held in caller provided buffers.
+//!
+//! A `Canvas` can hold a whole image or a band of its rows. With the
+//! `graphics` feature it is an embedded-graphics `DrawTarget`.
+
+use super::width_pixels_to_bytes;
+
+/// Color of a pixel. In RAM a set bit is white.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Color {
+    Black,
+    White,
+}
+
+impl Color {
+    fn byte(self) -> u8 {
+        match self {
+            Color::Black => 0x00,
+            Color::White => 0xff,
+        }
+    }
+}
+
+/// A 1 bit per pixel drawing surface over a caller provided buffer, packed
+/// the same as images given to `Screen::load_image`: rows of
+/// `width_pixels_to_bytes(width)` bytes, leftmost pixel in the MSB.
+///
+/// A canvas may hold only some rows, a band, of a taller image. Coordinates
+/// are always those of the whole image; pixels outside the rows held are
+/// ignored, as are pixels outside the image.
+pub struct Canvas<'a> {
+    buf: &'a mut [u8],
+    width: u16,
+    height: u16,
+    top: u16,
+    rows: u16,
+}
+
+impl<'a> Canvas<'a> {
+    /// A canvas of `width` pixels and as many rows as fit in `buf`.
+    pub fn new(buf: &'a mut [u8], width: u16) -> Canvas<'a> {
+        let rows = (buf.len() / width_pixels_to_bytes(width).max(1) as usize) as u16;
+        Canvas::new_band(buf, width, rows, 0)
+    }
+
+    /// A band of an image `height` pixels tall, holding the rows starting at
+    /// `top` that fit in `buf`.
+    pub fn new_band(buf: &'a mut [u8], width: u16, height: u16, top: u16) -> Canvas<'a> {
+        let row_bytes = width_pixels_to_bytes(width).max(1) as usize;
+        let fits = buf.len() / row_bytes;
+        let rows = fits.min(height.saturating_sub(top) as usize) as u16;
+
+        Canvas {
+            buf: &mut buf[..rows as usize * row_bytes],
+            width,
+            height,
+            top,
+            rows,
+        }
+    }
+
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    /// Height of the whole image.
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    /// First row held.
+    pub fn top(&self) -> u16 {
+        self.top
+    }
+
+    /// Number of rows held.
+    pub fn rows(&self) -> u16 {
+        self.rows
+    }
+
+    /// Whether the pixel at (`x`, `y`) is held.
+    pub fn contains(&self, x: u16, y: u16) -> bool {
+        x < self.width && y >= self.top && y - self.top < self.rows
+    }
+
+    /// The rows held, ready to be written to RAM.
+    pub fn as_bytes(&self) -> &[u8] {
+        self.buf
+    }
+
+    pub fn fill(&mut self, color: Color) {
+        for b in self.buf.iter_mut() {
+            *b = color.byte();
+        }
+    }
+
+    pub fn pixel(&self, x: u16, y: u16) -> Option<Color> {
+        if !self.contains(x, y) {
+            return None;
+        }
+        let (i, mask) = self.index(x, y);
+        Some(if self.buf[i] & mask != 0 { Color::White } else { Color::Black })
+    }
+
+    pub fn set_pixel(&mut self, x: u16, y: u16, color: Color) {
+        if !self.contains(x, y) {
+            return;
+        }
+        let (i, mask) = self.index(x, y);
+        match color {
+            Color::Black => self.buf[i] &= !mask,
+            Color::White => self.buf[i] |= mask,
+        }
+    }
+
+    /// Fills the rectangle of `w` by `h` pixels whose top left is (`x`, `y`),
+    /// clipped to the rows held.
+    pub fn fill_rect(&mut self, x: u16, y: u16, w: u16, h: u16, color: Color) {
+        let x_end = x.saturating_add(w).min(self.width);
+        let y_start = y.max(self.top);
+        let y_end = y.saturating_add(h).min(self.top + self.rows);
+        for y in y_start..y_end {
+            for x in x..x_end {
+                self.set_pixel(x, y, color);
+            }
+        }
+    }
+
+    fn index(&self, x: u16, y: u16) -> (usize, u8) {
+        let row_bytes = width_pixels_to_bytes(self.width) as usize;
+        let i = (y - self.top) as usize * row_bytes + x as usize / 8;
+        (i, 0x80 >> (x % 8))
+    }
+}
+
+#[cfg(feature = "graphics")]
+mod graphics {
+    use core::convert::Infallible;
+
+    use embedded_graphics_core::draw_target::DrawTarget;
+    use embedded_graphics_core::geometry::{OriginDimensions, Size};
+    use embedded_graphics_core::pixelcolor::BinaryColor;
+    use embedded_graphics_core::Pixel;
+
+    use super::{Canvas, Color};
+
+    /// `BinaryColor::On` is black, the ink on the paper.
+    impl From<BinaryColor> for Color {
+        fn from(color: BinaryColor) -> Color {
+            match color {
+                BinaryColor::On => Color::Black,
+                BinaryColor::Off => Color::White,
+            }
+        }
+    }
+
+    /// The size is that of the whole image, so drawables are clipped the same
+    /// in every band.
+    impl<'a> OriginDimensions for Canvas<'a> {
+        fn size(&self) -> Size {
+            Size::new(self.width as u32, self.height as u32)
+        }
+    }
+
+    impl<'a> DrawTarget for Canvas<'a> {
+        type Color = BinaryColor;
+        type Error = Infallible;
+
+        fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Infallible>
+        where
+            I: IntoIterator<Item = Pixel<BinaryColor>>,
+        {
+            for Pixel(point, color) in pixels {
+                if point.x >= 0 && point.y >= 0 && point.x <= 0xffff && point.y <= 0xffff {
+                    self.set_pixel(point.x as u16, point.y as u16, color.into());
+                }
+            }
+            Ok(())
+        }
+
+        fn clear(&mut self, color: BinaryColor) -> Result<(), Infallible> {
+            self.fill(color.into());
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,242 @@
+//! Compressed images, decompressed while they are written to RAM.
+//!
+//! Both formats are a sequence of blocks, each starting with a header byte.
+//!
+//! RLE, `Rle`:
+//!
+//! - `0x00..=0x7f`: the next `h + 1` bytes are copied as is.
+//! - `0x80..=0xff`: the next byte is repeated `h - 0x80 + 2` times.
+//!
+//! LZ, `Lz`, which also finds rows repeated from up to 256 bytes back:
+//!
+//! - `0x00..=0x7f`: the next `h + 1` bytes are copied as is.
+//! - `0x80..=0xff`: followed by a byte `d`; the `h - 0x80 + 3` bytes starting
+//!   `d + 1` bytes back in the output are copied. The copy may overlap the
+//!   bytes it produces.
+//!
+//! Decompressing needs no buffer for RLE and 256 bytes of stack for LZ. The
+//! encoders, `encode_rle` and `encode_lz`, need the `std` feature and are
+//! meant for build scripts.
+
+use super::ImageSource;
+
+#[cfg(feature = "std")]
+use std::vec::Vec;
+
+/// Size of the LZ window, the furthest back a match can refer to.
+pub const LZ_WINDOW: usize = 256;
+
+const MAX_LITERAL: usize = 0x80;
+const MAX_RLE_RUN: usize = 0x7f + 2;
+const MIN_LZ_MATCH: usize = 3;
+const MAX_LZ_MATCH: usize = 0x7f + 3;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CompressionError {
+    /// The data ends in the middle of a block.
+    LengthError,
+    /// An LZ match refers to before the start of the output.
+    ReferenceError,
+}
+
+/// An RLE compressed image. See the module documentation for the format.
+#[derive(Clone, Copy, Debug)]
+pub struct Rle<'a> {
+    data: &'a [u8],
+    len: usize,
+}
+
+impl<'a> Rle<'a> {
+    /// Checks that `data` is well formed and finds the decompressed length.
+    pub fn new(data: &'a [u8]) -> Result<Rle<'a>, CompressionError> {
+        let mut len = 0;
+        let mut i = 0;
+        while i < data.len() {
+            let header = data[i] as usize;
+            let (out, taken) = if header < 0x80 {
+                (header + 1, header + 1)
+            } else {
+                (header - 0x80 + 2, 1)
+            };
+            if i + 1 + taken > data.len() {
+                return Err(CompressionError::LengthError);
+            }
+            len += out;
+            i += 1 + taken;
+        }
+
+        Ok(Rle { data, len })
+    }
+}
+
+impl<'a> ImageSource for Rle<'a> {
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn stream<E, F>(&mut self, mut sink: F) -> Result<(), E>
+    where
+        F: FnMut(u8) -> Result<(), E>,
+    {
+        let mut i = 0;
+        while i < self.data.len() {
+            let header = self.data[i] as usize;
+            i += 1;
+            if header < 0x80 {
+                for &b in &self.data[i..i + header + 1] {
+                    sink(b)?;
+                }
+                i += header + 1;
+            } else {
+                for _ in 0..header - 0x80 + 2 {
+                    sink(self.data[i])?;
+                }
+                i += 1;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// An LZ compressed image. See the module documentation for the format.
+#[derive(Clone, Copy, Debug)]
+pub struct Lz<'a> {
+    data: &'a [u8],
+    len: usize,
+}
+
+impl<'a> Lz<'a> {
+    /// Checks that `data` is well formed and finds the decompressed length.
+    pub fn new(data: &'a [u8]) -> Result<Lz<'a>, CompressionError> {
+        let mut len = 0;
+        let mut i = 0;
+        while i < data.len() {
+            let header = data[i] as usize;
+            if header < 0x80 {
+                if i + 1 + header + 1 > data.len() {
+                    return Err(CompressionError::LengthError);
+                }
+                len += header + 1;
+                i += 1 + header + 1;
+            } else {
+                if i + 2 > data.len() {
+                    return Err(CompressionError::LengthError);
+                }
+                if data[i + 1] as usize + 1 > len {
+                    return Err(CompressionError::ReferenceError);
+                }
+                len += header - 0x80 + MIN_LZ_MATCH;
+                i += 2;
+            }
+        }
+
+        Ok(Lz { data, len })
+    }
+}
+
+impl<'a> ImageSource for Lz<'a> {
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn stream<E, F>(&mut self, mut sink: F) -> Result<(), E>
+    where
+        F: FnMut(u8) -> Result<(), E>,
+    {
+        let mut window = [0; LZ_WINDOW];
+        let mut out = 0;
+
+        let mut i = 0;
+        while i < self.data.len() {
+            let header = self.data[i] as usize;
+            if header < 0x80 {
+                for &b in &self.data[i + 1..i + 1 + header + 1] {
+                    window[out % LZ_WINDOW] = b;
+                    out += 1;
+                    sink(b)?;
+                }
+                i += 1 + header + 1;
+            } else {
+                let distance = self.data[i + 1] as usize + 1;
+                for _ in 0..header - 0x80 + MIN_LZ_MATCH {
+                    let b = window[(out - distance) % LZ_WINDOW];
+                    window[out % LZ_WINDOW] = b;
+                    out += 1;
+                    sink(b)?;
+                }
+                i += 2;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+fn push_literals(out: &mut Vec<u8>, literals: &[u8]) {
+    for chunk in literals.chunks(MAX_LITERAL) {
+        out.push((chunk.len() - 1) as u8);
+        out.extend_from_slice(chunk);
+    }
+}
+
+/// Compresses `data` for `Rle`.
+#[cfg(feature = "std")]
+pub fn encode_rle(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut literal_start = 0;
+    let mut i = 0;
+    while i < data.len() {
+        let mut run = 1;
+        while i + run < data.len() && run < MAX_RLE_RUN && data[i + run] == data[i] {
+            run += 1;
+        }
+        // two byte runs are no shorter than literals, and split them up
+        if run >= 3 {
+            push_literals(&mut out, &data[literal_start..i]);
+            out.push((run - 2 + 0x80) as u8);
+            out.push(data[i]);
+            i += run;
+            literal_start = i;
+        } else {
+            i += 1;
+        }
+    }
+    push_literals(&mut out, &data[literal_start..]);
+    out
+}
+
+/// Compresses `data` for `Lz`, taking the longest match at each position.
+#[cfg(feature = "std")]
+pub fn encode_lz(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut literal_start = 0;
+    let mut i = 0;
+    while i < data.len() {
+        let mut best = (0, 0);
+        for distance in 1..=i.min(LZ_WINDOW) {
+            let mut len = 0;
+            while i + len < data.len()
+                && len < MAX_LZ_MATCH
+                && data[i + len] == data[i + len - distance]
+            {
+                len += 1;
+            }
+            if len > best.0 {
+                best = (len, distance);
+            }
+        }
+
+        let (len, distance) = best;
+        if len >= MIN_LZ_MATCH {
+            push_literals(&mut out, &data[literal_start..i]);
+            out.push((len - MIN_LZ_MATCH + 0x80) as u8);
+            out.push((distance - 1) as u8);
+            i += len;
+            literal_start = i;
+        } else {
+            i += 1;
+        }
+    }
+    push_literals(&mut out, &data[literal_start..]);
+    out
+}
@@ -0,0 +1,233 @@
+//! A scrolling text terminal on the screen.
+//!
+//! `Console` draws text written to it with `write!` into a full screen
+//! image and shows the lines that changed on `flush`, choosing between
+//! partial updates and a full refresh with a `RefreshTracker`.
+
+use core::fmt;
+
+#[allow(deprecated)]
+use embedded_hal::digital::{InputPin, OutputPin};
+use embedded_hal::spi::FullDuplex;
+
+use super::font::{self, TextStyle};
+use super::{
+    Color, Dimensions, Refresh, RefreshTracker, Screen, ScreenError, Shadow, Window,
+};
+
+/// A text terminal drawn on a full screen image held in a caller provided
+/// buffer, for printing log lines with `write!`.
+///
+/// Text is drawn into the image as it is written and shown by `flush`, which
+/// sends the changed lines with a partial update, or the whole image with a
+/// full refresh when the `RefreshTracker` calls for one. Lines wrap at the
+/// right edge, and when the screen is full the text scrolls up a line.
+///
+/// Scrolling moves every line, so the update after it covers the whole text
+/// area.
+pub struct Console<'a> {
+    frame: Shadow<'a>,
+    style: TextStyle<'a>,
+    cols: u16,
+    rows: u16,
+    col: u16,
+    /// May be `rows`, past the last line, until the next character scrolls.
+    row: u16,
+    /// First and last lines changed since the last flush.
+    dirty: Option<(u16, u16)>,
+    tracker: RefreshTracker,
+}
+
+impl<'a> Console<'a> {
+    /// A blank console over `buf`, which must be `image_len(width, height)`
+    /// bytes. Returns `None` if it is the wrong length or not even one
+    /// character fits.
+    pub fn new(
+        buf: &'a mut [u8],
+        width: u16,
+        height: u16,
+        style: TextStyle<'a>,
+    ) -> Option<Console<'a>> {
+        let frame = Shadow::new(buf, width, height)?;
+        let line_spacing = style.font.line_spacing as i32 * style.scale.max(1) as i32;
+        let cols = style.fit(width);
+        let rows = (height as i32 + line_spacing) / style.line_height();
+        if cols == 0 || rows <= 0 {
+            return None;
+        }
+
+        let mut console = Console {
+            frame,
+            style,
+            cols: cols.min(u16::MAX as usize) as u16,
+            rows: rows as u16,
+            col: 0,
+            row: 0,
+            dirty: None,
+            tracker: RefreshTracker::default(),
+        };
+        console.clear();
+        Some(console)
+    }
+
+    /// Characters to a line.
+    pub fn cols(&self) -> u16 {
+        self.cols
+    }
+
+    /// Lines on the screen.
+    pub fn rows(&self) -> u16 {
+        self.rows
+    }
+
+    /// Column and line the next character is drawn at. The line is `rows()`
+    /// when the screen scrolls before the next character.
+    pub fn cursor(&self) -> (u16, u16) {
+        if self.col >= self.cols {
+            (0, self.row + 1)
+        } else {
+            (self.col, self.row)
+        }
+    }
+
+    /// The image shown once flushed.
+    pub fn as_bytes(&self) -> &[u8] {
+        self.frame.as_bytes()
+    }
+
+    /// For changing how often a full refresh is done.
+    pub fn tracker_mut(&mut self) -> &mut RefreshTracker {
+        &mut self.tracker
+    }
+
+    /// Blanks the screen and moves the cursor to the top left. The next
+    /// flush is a full refresh.
+    pub fn clear(&mut self) {
+        self.frame.canvas().fill(self.style.background.unwrap_or(Color::White));
+        self.col = 0;
+        self.row = 0;
+        self.dirty = Some((0, self.rows - 1));
+        self.tracker.force_full();
+    }
+
+    /// Shows the lines changed since the last flush, returning the kind of
+    /// update done, or `None` if nothing changed.
+    #[allow(deprecated)]
+    pub fn flush<SPI, DC, CS, BUSY, RST, ERR, D>(
+        &mut self,
+        screen: &mut Screen<SPI, DC, CS, BUSY, RST, ERR, D>,
+    ) -> Result<Option<Refresh>, ScreenError<ERR>>
+    where
+        SPI: FullDuplex<u8, Error = ERR>,
+        DC: OutputPin,
+        CS: OutputPin,
+        BUSY: InputPin,
+        RST: OutputPin,
+        D: Dimensions,
+    {
+        let (first, last) = match self.dirty {
+            Some(dirty) => dirty,
+            None => return Ok(None),
+        };
+
+        let line_height = self.style.line_height() as u32;
+        let height = self.frame.height() as u32;
+        let y = first as u32 * line_height;
+        let window = Window {
+            x: 0,
+            y: y as u16,
+            w: self.frame.width(),
+            h: ((last as u32 + 1) * line_height).min(height).saturating_sub(y) as u16,
+        };
+
+        let changed_pixels = window.w as u32 * window.h as u32;
+        let refresh = self.tracker.next(changed_pixels);
+        match refresh {
+            Refresh::Full => screen.show_full_screen_source(self.frame.as_bytes())?,
+            Refresh::Partial => screen.update_windows(self.frame.as_bytes(), &[window])?,
+        }
+        self.tracker.record(refresh, changed_pixels);
+        self.dirty = None;
+
+        Ok(Some(refresh))
+    }
+
+    fn put(&mut self, c: char) {
+        match c {
+            '\n' => {
+                // scroll for the last newline, so the row stays at most
+                // `rows` and each newline moves the text by one line
+                if self.row >= self.rows {
+                    self.scroll();
+                }
+                self.col = 0;
+                self.row += 1;
+                return;
+            }
+            '\r' => {
+                self.col = 0;
+                return;
+            }
+            _ => {}
+        }
+
+        if self.col >= self.cols {
+            self.col = 0;
+            self.row += 1;
+        }
+        if self.row >= self.rows {
+            self.scroll();
+        }
+
+        let style = TextStyle {
+            background: Some(self.style.background.unwrap_or(Color::White)),
+            ..self.style
+        };
+        let x = self.col as i32 * style.advance();
+        let y = self.row as i32 * style.line_height();
+        let mut buf = [0; 4];
+        let c = if c == '\t' { " " } else { c.encode_utf8(&mut buf) };
+        font::draw_text(&mut self.frame.canvas(), x, y, c, &style);
+
+        self.mark(self.row, self.row);
+        self.col += 1;
+    }
+
+    /// Moves every line up one, blanking the last.
+    fn scroll(&mut self) {
+        let row_bytes = self.frame.as_bytes().len() / self.frame.height() as usize;
+        let line_bytes = self.style.line_height() as usize * row_bytes;
+        let text_bytes = self.rows as usize * line_bytes;
+
+        let bytes = self.frame.as_mut_bytes();
+        let end = text_bytes.min(bytes.len());
+        if line_bytes < end {
+            bytes.copy_within(line_bytes..end, 0);
+        }
+
+        let top = (self.rows - 1) as i32 * self.style.line_height();
+        let background = self.style.background.unwrap_or(Color::White);
+        let mut canvas = self.frame.canvas();
+        let (width, height) = (canvas.width(), canvas.height());
+        canvas.fill_rect(0, top as u16, width, height - top as u16, background);
+
+        self.row = self.rows - 1;
+        self.mark(0, self.rows - 1);
+    }
+
+    fn mark(&mut self, first: u16, last: u16) {
+        self.dirty = Some(match self.dirty {
+            Some((a, b)) => (a.min(first), b.max(last)),
+            None => (first, last),
+        });
+    }
+}
+
+impl<'a> fmt::Write for Console<'a> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for c in s.chars() {
+            self.put(c);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,303 @@
+//! Decoding of captured command streams.
+//!
+//! A `Decoder` is fed the bytes seen on the bus along with the level of DC
+//! when they were clocked, and produces an `Event` for each command with its
+//! parameters decoded by the matching type in `params`. The `Display` impl
+//! of `Event` prints one line per command and is meant for debugging traces.
+
+use core::convert::TryFrom;
+use core::fmt;
+
+use super::params::{
+    BorderWaveform, DataEntryMode, DeepSleep, DriverOutput, DummyLinePeriod, GateLineWidth,
+    GateScanStart, Lut, ParamError, Parameters, RamXCounter, RamXWindow, RamYCounter, RamYWindow,
+    SoftStart, Temperature, UpdateOptions, UpdateSequence, Vcom,
+};
+use super::Command;
+
+/// Most parameter bytes taken by any command other than `Command::WriteRam`.
+const MAX_PARAMS: usize = 30;
+
+/// A decoded command.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Event {
+    DriverOutputControl(DriverOutput),
+    BoosterSoftStartControl(SoftStart),
+    GateScanStartPosition(GateScanStart),
+    DeepSleepMode(DeepSleep),
+    DataEntryModeSetting(DataEntryMode),
+    SwReset,
+    TemperatureSensorControl(Temperature),
+    MasterActivation,
+    DisplayUpdateControl1(UpdateOptions),
+    DisplayUpdateControl2(UpdateSequence),
+    /// `len` is the number of bytes written to RAM.
+    WriteRam { len: usize },
+    WriteVcomRegister(Vcom),
+    WriteLutRegister(Lut),
+    SetDummyLinePeriod(DummyLinePeriod),
+    SetGateLineWidth(GateLineWidth),
+    BorderWaveformControl(BorderWaveform),
+    SetRamXAddressStartEndPosition(RamXWindow),
+    SetRamYAddressStartEndPosition(RamYWindow),
+    SetRamXAddressCounter(RamXCounter),
+    SetRamYAddressCounter(RamYCounter),
+    Nop,
+    /// An opcode not listed in `Command`, followed by `len` data bytes.
+    Unknown { opcode: u8, len: usize },
+    /// A known opcode followed by `len` data bytes that do not decode, being
+    /// the wrong number of bytes or having reserved bits set.
+    Malformed { opcode: u8, len: usize, error: ParamError },
+    /// Data bytes sent before any command.
+    OrphanData { len: usize },
+}
+
+/// Incrementally decodes (DC, byte) pairs into `Event`s.
+///
+/// A command is only known to be complete once the next command starts, so
+/// the event for a command is returned when the following command byte is
+/// pushed, or by `finish` at the end of a capture.
+#[derive(Clone, Debug)]
+pub struct Decoder {
+    opcode: Option<u8>,
+    params: [u8; MAX_PARAMS],
+    len: usize,
+}
+
+impl Decoder {
+    pub fn new() -> Decoder {
+        Decoder {
+            opcode: None,
+            params: [0; MAX_PARAMS],
+            len: 0,
+        }
+    }
+
+    /// Feeds a byte clocked with DC at the given level, high being data.
+    pub fn push(&mut self, dc: bool, byte: u8) -> Option<Event> {
+        if dc {
+            if self.len < MAX_PARAMS {
+                self.params[self.len] = byte;
+            }
+            self.len += 1;
+            None
+        } else {
+            let event = self.finish();
+            self.opcode = Some(byte);
+            event
+        }
+    }
+
+    /// Returns the event for the command in progress, if any, and resets the
+    /// decoder.
+    pub fn finish(&mut self) -> Option<Event> {
+        let event = match self.opcode {
+            Some(opcode) => Some(decode_command(opcode, &self.params, self.len)),
+            None if self.len > 0 => Some(Event::OrphanData { len: self.len }),
+            None => None,
+        };
+
+        self.opcode = None;
+        self.len = 0;
+
+        event
+    }
+}
+
+impl Default for Decoder {
+    fn default() -> Decoder {
+        Decoder::new()
+    }
+}
+
+fn decode_command(opcode: u8, p: &[u8; MAX_PARAMS], len: usize) -> Event {
+    let command = match Command::try_from(opcode) {
+        Ok(command) => command,
+        Err(_) => return Event::Unknown { opcode, len },
+    };
+    let data = match command {
+        // only RAM writes are longer than `p`
+        Command::WriteRam => return Event::WriteRam { len },
+        _ if len > MAX_PARAMS => {
+            return Event::Malformed { opcode, len, error: ParamError::LengthError }
+        }
+        _ => &p[..len],
+    };
+
+    let event = match command {
+        Command::DriverOutputControl => DriverOutput::decode(data).map(Event::DriverOutputControl),
+        Command::BoosterSoftStartControl => {
+            SoftStart::decode(data).map(Event::BoosterSoftStartControl)
+        }
+        Command::GateScanStartPosition => {
+            GateScanStart::decode(data).map(Event::GateScanStartPosition)
+        }
+        Command::DeepSleepMode => DeepSleep::decode(data).map(Event::DeepSleepMode),
+        Command::DataEntryModeSetting => DataEntryMode::decode(data).map(Event::DataEntryModeSetting),
+        Command::SwReset => no_data(data, Event::SwReset),
+        Command::TemperatureSensorControl => {
+            Temperature::decode(data).map(Event::TemperatureSensorControl)
+        }
+        Command::MasterActivation => no_data(data, Event::MasterActivation),
+        Command::DisplayUpdateControl1 => {
+            UpdateOptions::decode(data).map(Event::DisplayUpdateControl1)
+        }
+        Command::DisplayUpdateControl2 => {
+            UpdateSequence::decode(data).map(Event::DisplayUpdateControl2)
+        }
+        Command::WriteRam => unreachable!(),
+        Command::WriteVcomRegister => Vcom::decode(data).map(Event::WriteVcomRegister),
+        Command::WriteLutRegister => Lut::decode(data).map(Event::WriteLutRegister),
+        Command::SetDummyLinePeriod => DummyLinePeriod::decode(data).map(Event::SetDummyLinePeriod),
+        Command::SetGateLineWidth => GateLineWidth::decode(data).map(Event::SetGateLineWidth),
+        Command::BorderWaveformControl => {
+            BorderWaveform::decode(data).map(Event::BorderWaveformControl)
+        }
+        Command::SetRamXAddressStartEndPosition => {
+            RamXWindow::decode(data).map(Event::SetRamXAddressStartEndPosition)
+        }
+        Command::SetRamYAddressStartEndPosition => {
+            RamYWindow::decode(data).map(Event::SetRamYAddressStartEndPosition)
+        }
+        Command::SetRamXAddressCounter => RamXCounter::decode(data).map(Event::SetRamXAddressCounter),
+        Command::SetRamYAddressCounter => RamYCounter::decode(data).map(Event::SetRamYAddressCounter),
+        Command::Nop => no_data(data, Event::Nop),
+    };
+
+    event.unwrap_or_else(|error| Event::Malformed { opcode, len, error })
+}
+
+/// `event` for a command that takes no data.
+fn no_data(data: &[u8], event: Event) -> Result<Event, ParamError> {
+    if data.is_empty() {
+        Ok(event)
+    } else {
+        Err(ParamError::LengthError)
+    }
+}
+
+/// An iterator of the events decoded from an iterator of (DC, byte) pairs.
+pub struct Decode<I> {
+    bytes: I,
+    decoder: Decoder,
+    done: bool,
+}
+
+/// Decodes a whole capture of (DC, byte) pairs.
+pub fn decode<I>(bytes: I) -> Decode<I::IntoIter>
+where
+    I: IntoIterator<Item = (bool, u8)>,
+{
+    Decode {
+        bytes: bytes.into_iter(),
+        decoder: Decoder::new(),
+        done: false,
+    }
+}
+
+impl<I> Iterator for Decode<I>
+where
+    I: Iterator<Item = (bool, u8)>,
+{
+    type Item = Event;
+
+    fn next(&mut self) -> Option<Event> {
+        if self.done {
+            return None;
+        }
+
+        for (dc, byte) in self.bytes.by_ref() {
+            if let Some(event) = self.decoder.push(dc, byte) {
+                return Some(event);
+            }
+        }
+
+        self.done = true;
+        self.decoder.finish()
+    }
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Event::DriverOutputControl(DriverOutput { gates, gate_scan }) => write!(
+                f,
+                "DriverOutputControl gates={} gd={} sm={} tb={}",
+                gates, gate_scan.first_gate as u8, gate_scan.order as u8, gate_scan.direction as u8
+            ),
+            Event::BoosterSoftStartControl(SoftStart { phase1, phase2, phase3 }) => write!(
+                f,
+                "BoosterSoftStartControl phase1=0x{:02x} phase2=0x{:02x} phase3=0x{:02x}",
+                phase1, phase2, phase3
+            ),
+            Event::GateScanStartPosition(GateScanStart { position }) => {
+                write!(f, "GateScanStartPosition position={}", position)
+            }
+            Event::DeepSleepMode(DeepSleep { enabled }) => {
+                write!(f, "DeepSleepMode enabled={}", enabled as u8)
+            }
+            Event::DataEntryModeSetting(DataEntryMode { id, am }) => {
+                write!(f, "DataEntryModeSetting id=0b{:02b} am={}", id as u8, am as u8)
+            }
+            Event::SwReset => write!(f, "SwReset"),
+            Event::TemperatureSensorControl(Temperature { sixteenths }) => write!(
+                f,
+                "TemperatureSensorControl temperature={}/16 C",
+                sixteenths
+            ),
+            Event::MasterActivation => write!(f, "MasterActivation"),
+            Event::DisplayUpdateControl1(UpdateOptions { bypass, bypass_value, c, gs }) => write!(
+                f,
+                "DisplayUpdateControl1 bypass={} bypass_value={} c=0b{:02b} gs=0b{:02b}",
+                bypass as u8, bypass_value as u8, c, gs
+            ),
+            Event::DisplayUpdateControl2(sequence) => {
+                write!(f, "DisplayUpdateControl2 sequence=0x{:02x}", sequence.bits())
+            }
+            Event::WriteRam { len } => write!(f, "WriteRam len={}", len),
+            Event::WriteVcomRegister(Vcom { value }) => {
+                write!(f, "WriteVcomRegister vcom=0x{:02x}", value)
+            }
+            Event::WriteLutRegister(Lut(ref lut)) => {
+                write!(f, "WriteLutRegister lut=")?;
+                for byte in lut.iter() {
+                    write!(f, "{:02x}", byte)?;
+                }
+                Ok(())
+            }
+            Event::SetDummyLinePeriod(DummyLinePeriod { period }) => {
+                write!(f, "SetDummyLinePeriod period=0x{:02x}", period)
+            }
+            Event::SetGateLineWidth(GateLineWidth { width }) => {
+                write!(f, "SetGateLineWidth width=0x{:02x}", width)
+            }
+            Event::BorderWaveformControl(BorderWaveform { follow_source, fix_level, level, gs }) => {
+                write!(
+                    f,
+                    "BorderWaveformControl follow_source={} fix_level={} level=0b{:02b} gs=0b{:02b}",
+                    follow_source as u8, fix_level as u8, level as u8, gs
+                )
+            }
+            Event::SetRamXAddressStartEndPosition(RamXWindow { start, end }) => {
+                write!(f, "SetRamXAddressStartEndPosition start={} end={}", start, end)
+            }
+            Event::SetRamYAddressStartEndPosition(RamYWindow { start, end }) => {
+                write!(f, "SetRamYAddressStartEndPosition start={} end={}", start, end)
+            }
+            Event::SetRamXAddressCounter(RamXCounter { x }) => {
+                write!(f, "SetRamXAddressCounter x={}", x)
+            }
+            Event::SetRamYAddressCounter(RamYCounter { y }) => {
+                write!(f, "SetRamYAddressCounter y={}", y)
+            }
+            Event::Nop => write!(f, "Nop"),
+            Event::Unknown { opcode, len } => write!(f, "Unknown opcode=0x{:02x} len={}", opcode, len),
+            Event::Malformed { opcode, len, error } => write!(
+                f,
+                "Malformed opcode=0x{:02x} len={} error={:?}",
+                opcode, len, error
+            ),
+            Event::OrphanData { len } => write!(f, "OrphanData len={}", len),
+        }
+    }
+}
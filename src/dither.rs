@@ -0,0 +1,251 @@
+//! Conversion of 8 bit grayscale, 0 black, to packed 1 bit per pixel images.
+//!
+//! Rows are converted one at a time by a `Ditherer`, so an image never has to
+//! be held in memory as a whole. Error diffusion carries error to the next
+//! two rows in a caller provided buffer of `scratch_len(width)` values; the
+//! other methods need none.
+
+use super::{width_pixels_to_bytes, ImageSource};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Dither {
+    /// Gray levels at or above the value are white.
+    Threshold(u8),
+    /// Ordered dithering with a 4x4 Bayer matrix.
+    Bayer,
+    /// Error diffusion to four neighbours, 7/16 to the right.
+    FloydSteinberg,
+    /// Error diffusion of 6/8 of the error to six neighbours. Keeps more
+    /// contrast than Floyd-Steinberg.
+    Atkinson,
+}
+
+impl Dither {
+    fn diffuses(self) -> bool {
+        match self {
+            Dither::FloydSteinberg | Dither::Atkinson => true,
+            Dither::Threshold(_) | Dither::Bayer => false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DitherError {
+    /// A row is not `width` pixels or `width_pixels_to_bytes(width)` bytes.
+    LengthError,
+    /// The scratch buffer is shorter than `scratch_len(width)`.
+    ScratchError,
+}
+
+const BAYER: [[u8; 4]; 4] = [
+    [0, 8, 2, 10],
+    [12, 4, 14, 6],
+    [3, 11, 1, 9],
+    [15, 7, 13, 5],
+];
+
+/// Rows of error held for error diffusion: the current row and the two
+/// below it.
+const ERROR_ROWS: usize = 3;
+/// Columns of error held beyond each side of a row.
+const ERROR_MARGIN: usize = 2;
+
+/// Length of the scratch buffer needed for error diffusion of rows `width`
+/// pixels wide.
+pub const fn scratch_len(width: u16) -> usize {
+    ERROR_ROWS * (width as usize + 2 * ERROR_MARGIN)
+}
+
+/// Converts rows top to bottom.
+pub struct Ditherer<'a> {
+    method: Dither,
+    width: u16,
+    scratch: &'a mut [i16],
+    y: usize,
+}
+
+impl<'a> Ditherer<'a> {
+    /// `scratch` is only used by the error diffusion methods and may be empty
+    /// for the others.
+    pub fn new(
+        method: Dither,
+        width: u16,
+        scratch: &'a mut [i16],
+    ) -> Result<Ditherer<'a>, DitherError> {
+        if method.diffuses() && scratch.len() < scratch_len(width) {
+            return Err(DitherError::ScratchError);
+        }
+
+        let mut ditherer = Ditherer {
+            method,
+            width,
+            scratch,
+            y: 0,
+        };
+        ditherer.reset();
+        Ok(ditherer)
+    }
+
+    /// Starts again from the top row.
+    pub fn reset(&mut self) {
+        self.y = 0;
+        for e in self.scratch.iter_mut() {
+            *e = 0;
+        }
+    }
+
+    /// Converts the next row of `width` gray levels into
+    /// `width_pixels_to_bytes(width)` bytes. Unused bits of the last byte are
+    /// set.
+    pub fn row(&mut self, gray: &[u8], out: &mut [u8]) -> Result<(), DitherError> {
+        let row_bytes = width_pixels_to_bytes(self.width) as usize;
+        if gray.len() != self.width as usize || out.len() != row_bytes {
+            return Err(DitherError::LengthError);
+        }
+
+        self.row_unchecked(gray, out);
+        Ok(())
+    }
+
+    fn row_unchecked(&mut self, gray: &[u8], out: &mut [u8]) {
+        for b in out.iter_mut() {
+            *b = 0xff;
+        }
+
+        match self.method {
+            Dither::Threshold(level) => {
+                for (x, &g) in gray.iter().enumerate() {
+                    set(out, x, g >= level);
+                }
+            }
+            Dither::Bayer => {
+                let matrix = BAYER[self.y % 4];
+                for (x, &g) in gray.iter().enumerate() {
+                    set(out, x, g >= matrix[x % 4] * 16 + 8);
+                }
+            }
+            Dither::FloydSteinberg | Dither::Atkinson => self.diffuse(gray, out),
+        }
+
+        self.y += 1;
+    }
+
+    fn diffuse(&mut self, gray: &[u8], out: &mut [u8]) {
+        let stride = self.width as usize + 2 * ERROR_MARGIN;
+        let row = |n: usize| (n % ERROR_ROWS) * stride + ERROR_MARGIN;
+        let (cur, next, after) = (row(self.y), row(self.y + 1), row(self.y + 2));
+        let e = &mut *self.scratch;
+
+        for (x, &g) in gray.iter().enumerate() {
+            let value = g as i16 + e[cur + x];
+            let white = value >= 128;
+            set(out, x, white);
+
+            let error = value - if white { 255 } else { 0 };
+            if self.method == Dither::FloydSteinberg {
+                e[cur + x + 1] += error * 7 / 16;
+                e[next + x - 1] += error * 3 / 16;
+                e[next + x] += error * 5 / 16;
+                e[next + x + 1] += error / 16;
+            } else {
+                let error = error / 8;
+                e[cur + x + 1] += error;
+                e[cur + x + 2] += error;
+                e[next + x - 1] += error;
+                e[next + x] += error;
+                e[next + x + 1] += error;
+                e[after + x] += error;
+            }
+        }
+
+        // the current row becomes the one after next
+        for v in &mut e[cur - ERROR_MARGIN..cur - ERROR_MARGIN + stride] {
+            *v = 0;
+        }
+    }
+}
+
+fn set(out: &mut [u8], x: usize, white: bool) {
+    if !white {
+        out[x / 8] &= !(0x80 >> (x % 8));
+    }
+}
+
+/// Converts a whole image of `width` by `gray.len() / width` pixels.
+pub fn dither(
+    method: Dither,
+    width: u16,
+    gray: &[u8],
+    out: &mut [u8],
+    scratch: &mut [i16],
+) -> Result<(), DitherError> {
+    let row_bytes = width_pixels_to_bytes(width) as usize;
+    if width == 0
+        || gray.len() % width as usize != 0
+        || out.len() != gray.len() / width as usize * row_bytes
+    {
+        return Err(DitherError::LengthError);
+    }
+
+    let mut ditherer = Ditherer::new(method, width, scratch)?;
+    for (gray, out) in gray.chunks(width as usize).zip(out.chunks_mut(row_bytes)) {
+        ditherer.row_unchecked(gray, out);
+    }
+    Ok(())
+}
+
+/// Dithers rows of gray levels as they are read, one row at a time.
+pub struct Dithered<'a, F> {
+    ditherer: Ditherer<'a>,
+    gray: &'a mut [u8],
+    out: &'a mut [u8],
+    rows: u16,
+    read: F,
+}
+
+impl<'a, F: FnMut(u16, &mut [u8])> Dithered<'a, F> {
+    /// `read` is called with the index of each row, from 0 to `rows - 1`, and
+    /// fills `gray` with its gray levels. `gray` and `out` hold a row before
+    /// and after dithering.
+    pub fn new(
+        ditherer: Ditherer<'a>,
+        gray: &'a mut [u8],
+        out: &'a mut [u8],
+        rows: u16,
+        read: F,
+    ) -> Result<Dithered<'a, F>, DitherError> {
+        let width = ditherer.width;
+        if gray.len() != width as usize || out.len() != width_pixels_to_bytes(width) as usize {
+            return Err(DitherError::LengthError);
+        }
+
+        Ok(Dithered {
+            ditherer,
+            gray,
+            out,
+            rows,
+            read,
+        })
+    }
+}
+
+impl<'a, F: FnMut(u16, &mut [u8])> ImageSource for Dithered<'a, F> {
+    fn len(&self) -> usize {
+        self.out.len() * self.rows as usize
+    }
+
+    fn stream<E, S>(&mut self, mut sink: S) -> Result<(), E>
+    where
+        S: FnMut(u8) -> Result<(), E>,
+    {
+        self.ditherer.reset();
+        for y in 0..self.rows {
+            (self.read)(y, self.gray);
+            self.ditherer.row_unchecked(self.gray, self.out);
+            for &b in self.out.iter() {
+                sink(b)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,688 @@
+//! A software model of the controller for running display code on a host.
+//! Enabled with the `emulator` feature.
+//!
+//! The emulator implements the HAL traits used by `Screen` and interprets the
+//! command stream sent through them: the RAM window, address counters, data
+//! entry mode, RAM writes, and the display update sequence. Activating a
+//! sequence with the Display Pattern stage copies controller RAM onto the
+//! visible panel image and holds BUSY high for a number of polls.
+//!
+//! RAM is laid out as the controller addresses it, 32 bytes by 300 rows,
+//! with the MSB of each byte being the leftmost pixel. A set bit is white.
+//! RAM row n is shown on the nth gate in the scan order selected with
+//! `Command::DriverOutputControl`. By default the last gate is the top row of
+//! the visible image, as on the CFAP200200 panels, which is why their presets
+//! write RAM with Y decrementing.
+//!
+//! ```ignore
+//! let (emulator, mut screen) =
+//!     Emulator::screen(ScreenBuilder::preset(Preset::CFAP200200A0_154))?;
+//! screen.show_full_screen_image(&image)?;
+//! emulator.save_png("frame.png")?;
+//! ```
+
+use core::cell::RefCell;
+use core::convert::{Infallible, TryFrom};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::rc::Rc;
+use std::string::String;
+use std::vec::Vec;
+
+use embedded_hal::blocking::delay::DelayMs;
+#[allow(deprecated)]
+use embedded_hal::digital::{InputPin, OutputPin};
+use embedded_hal::spi::FullDuplex;
+
+use super::params::{
+    AddressDirection, DataEntryMode, DeepSleep, DriverOutput, Lut, ParamError, Parameters,
+    RamXCounter, RamXWindow, RamYCounter, RamYWindow, UpdateSequence,
+};
+use super::{
+    width_pixels_to_bytes, Command, EntryMode, FirstGate, GateOrder, GateScan, ScanDirection,
+    Screen, ScreenBuilder, ScreenError, MAX_X_SIZE, MAX_Y_SIZE,
+};
+
+/// Width of controller RAM in bytes.
+pub const RAM_X_BYTES: usize = MAX_X_SIZE as usize / 8;
+/// Height of controller RAM in rows.
+pub const RAM_Y_ROWS: usize = MAX_Y_SIZE as usize;
+
+/// Number of polls BUSY reads high for after `Command::MasterActivation`,
+/// unless changed with `Emulator::set_busy_polls`.
+pub const DEFAULT_BUSY_POLLS: usize = 8;
+
+/// A `Screen` driving an `Emulator`.
+pub type EmulatedScreen<D = super::DynamicSize> =
+    Screen<EmuSpi, EmuPin, EmuPin, EmuBusy, EmuPin, Infallible, D>;
+
+#[derive(Clone, Copy, Debug)]
+struct Registers {
+    driver_output: DriverOutput,
+    data_entry: DataEntryMode,
+    x_window: RamXWindow,
+    y_window: RamYWindow,
+    x_counter: u8,
+    y_counter: u16,
+    update_sequence: UpdateSequence,
+    lut: Lut,
+    deep_sleep: bool,
+}
+
+impl Registers {
+    const POR: Registers = Registers {
+        driver_output: DriverOutput {
+            gates: 0x12b + 1,
+            gate_scan: GateScan::POR,
+        },
+        data_entry: DataEntryMode {
+            id: EntryMode::XIncrementYIncrement,
+            am: AddressDirection::X,
+        },
+        x_window: RamXWindow {
+            start: 0x00,
+            end: 0x18,
+        },
+        y_window: RamYWindow {
+            start: 0x000,
+            end: 0x12b,
+        },
+        x_counter: 0x00,
+        y_counter: 0x000,
+        update_sequence: UpdateSequence::EMPTY,
+        lut: Lut([0; 30]),
+        deep_sleep: false,
+    };
+}
+
+#[derive(Debug)]
+struct State {
+    x_size: u16,
+    y_size: u16,
+    gate_zero_at_top: bool,
+
+    dc: bool,
+    cs: bool,
+    reset: bool,
+
+    regs: Registers,
+    ram: Vec<u8>,
+    visible: Vec<u8>,
+
+    /// Command currently receiving data and the data received so far.
+    command: Option<u8>,
+    params: Vec<u8>,
+
+    clock_enabled: bool,
+    analog_enabled: bool,
+    busy: usize,
+    busy_polls: usize,
+    pending_reads: usize,
+
+    updates: usize,
+    errors: Vec<String>,
+}
+
+impl State {
+    fn new(x_size: u16, y_size: u16) -> State {
+        State {
+            x_size,
+            y_size,
+            gate_zero_at_top: false,
+
+            dc: true,
+            cs: true,
+            reset: true,
+
+            regs: Registers::POR,
+            ram: vec![0xff; RAM_X_BYTES * RAM_Y_ROWS],
+            visible: vec![0xff; RAM_X_BYTES * RAM_Y_ROWS],
+
+            command: None,
+            params: Vec::new(),
+
+            clock_enabled: false,
+            analog_enabled: false,
+            busy: 0,
+            busy_polls: DEFAULT_BUSY_POLLS,
+            pending_reads: 0,
+
+            updates: 0,
+            errors: Vec::new(),
+        }
+    }
+
+    fn error(&mut self, msg: String) {
+        self.errors.push(msg);
+    }
+
+    fn reset_registers(&mut self) {
+        let deep_sleep = self.regs.deep_sleep;
+        self.regs = Registers::POR;
+        self.regs.deep_sleep = deep_sleep;
+        self.command = None;
+        self.params.clear();
+    }
+
+    fn receive(&mut self, byte: u8) {
+        if self.cs {
+            self.error(format!("byte 0x{:02x} sent while CS was high", byte));
+            return;
+        }
+        if !self.reset {
+            self.error(format!("byte 0x{:02x} sent while in reset", byte));
+            return;
+        }
+        if self.busy > 0 {
+            self.error(format!("byte 0x{:02x} sent while BUSY was high", byte));
+        }
+
+        if self.dc {
+            self.data(byte);
+        } else {
+            self.command(byte);
+        }
+    }
+
+    fn command(&mut self, cmd: u8) {
+        if self.regs.deep_sleep {
+            self.error(format!("command 0x{:02x} sent in deep sleep", cmd));
+            return;
+        }
+
+        self.command = Some(cmd);
+        self.params.clear();
+
+        match Command::try_from(cmd) {
+            Ok(Command::SwReset) => self.reset_registers(),
+            Ok(Command::MasterActivation) => self.activate(),
+            _ => {}
+        }
+    }
+
+    fn data(&mut self, byte: u8) {
+        let cmd = match self.command {
+            Some(cmd) => cmd,
+            None => {
+                self.error(format!("data 0x{:02x} sent without a command", byte));
+                return;
+            }
+        };
+
+        let command = match Command::try_from(cmd) {
+            Ok(command) => command,
+            Err(_) => return,
+        };
+        if let Command::WriteRam = command {
+            self.write_ram(byte);
+            return;
+        }
+
+        self.params.push(byte);
+        // registers are set once all of the data of a command is received
+        match self.set_registers(command) {
+            Ok(()) | Err(ParamError::LengthError) => {}
+            Err(ParamError::ReservedError) => self.error(format!(
+                "{:?} data {:02x?} has reserved bits set",
+                command, self.params
+            )),
+        }
+    }
+
+    fn set_registers(&mut self, command: Command) -> Result<(), ParamError> {
+        let p = &self.params;
+        let regs = &mut self.regs;
+        match command {
+            Command::DriverOutputControl => regs.driver_output = DriverOutput::decode(p)?,
+            Command::DeepSleepMode => regs.deep_sleep = DeepSleep::decode(p)?.enabled,
+            Command::DataEntryModeSetting => regs.data_entry = DataEntryMode::decode(p)?,
+            Command::DisplayUpdateControl2 => regs.update_sequence = UpdateSequence::decode(p)?,
+            Command::WriteLutRegister => regs.lut = Lut::decode(p)?,
+            Command::SetRamXAddressStartEndPosition => regs.x_window = RamXWindow::decode(p)?,
+            Command::SetRamYAddressStartEndPosition => regs.y_window = RamYWindow::decode(p)?,
+            Command::SetRamXAddressCounter => regs.x_counter = RamXCounter::decode(p)?.x,
+            Command::SetRamYAddressCounter => regs.y_counter = RamYCounter::decode(p)?.y,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn write_ram(&mut self, byte: u8) {
+        let x = self.regs.x_counter as usize;
+        let y = self.regs.y_counter as usize;
+        if x < RAM_X_BYTES && y < RAM_Y_ROWS {
+            self.ram[y * RAM_X_BYTES + x] = byte;
+        } else {
+            self.error(format!("RAM write outside of RAM at ({}, {})", x, y));
+        }
+
+        let regs = &mut self.regs;
+        let id = regs.data_entry.id as u8;
+        let x_inc = id & 0b01 != 0;
+        let y_inc = id & 0b10 != 0;
+        let (x, y) = (regs.x_window, regs.y_window);
+        if regs.data_entry.am == AddressDirection::Y {
+            if step(&mut regs.y_counter, y.start, y.end, y_inc) {
+                step(&mut regs.x_counter, x.start, x.end, x_inc);
+            }
+        } else if step(&mut regs.x_counter, x.start, x.end, x_inc) {
+            step(&mut regs.y_counter, y.start, y.end, y_inc);
+        }
+    }
+
+    fn activate(&mut self) {
+        let seq = self.regs.update_sequence;
+
+        if seq.contains(UpdateSequence::CLOCK_ENABLE) {
+            self.clock_enabled = true;
+        }
+        if seq.contains(UpdateSequence::ANALOG_ENABLE) {
+            self.analog_enabled = true;
+        }
+        if seq.contains(UpdateSequence::DISPLAY_PATTERN) {
+            if self.clock_enabled && self.analog_enabled {
+                self.display();
+            } else {
+                self.error(String::from("display pattern with the clock or analog disabled"));
+            }
+        }
+        if seq.contains(UpdateSequence::ANALOG_DISABLE) {
+            self.analog_enabled = false;
+        }
+        if seq.contains(UpdateSequence::CLOCK_DISABLE) {
+            self.clock_enabled = false;
+        }
+
+        self.busy = self.busy_polls;
+    }
+
+    /// Gate driving row `y` of the visible image.
+    fn gate(&self, y: u16) -> usize {
+        if self.gate_zero_at_top {
+            y as usize
+        } else {
+            (self.y_size - 1 - y) as usize
+        }
+    }
+
+    /// Gate RAM row `row` is shown on, or `None` if it is not one of the
+    /// `mux` gates scanned. With an odd `mux` and GD set the last row swaps
+    /// onto the gate after them.
+    fn gate_for_row(&self, row: usize) -> Option<usize> {
+        let mux = self.regs.driver_output.gates as usize;
+        let gate_scan = self.regs.driver_output.gate_scan;
+        let mut gate = row;
+        if gate_scan.order == GateOrder::Separated {
+            let half = (mux + 1) / 2;
+            gate = if row < half { row * 2 } else { (row - half) * 2 + 1 };
+        }
+        if gate_scan.first_gate == FirstGate::G1 {
+            gate ^= 1;
+        }
+        if gate >= mux {
+            return None;
+        }
+        if gate_scan.direction == ScanDirection::Reverse {
+            gate = mux - 1 - gate;
+        }
+        Some(gate)
+    }
+
+    fn display(&mut self) {
+        let mux = (self.regs.driver_output.gates as usize).min(RAM_Y_ROWS);
+        for row in 0..mux {
+            let gate = match self.gate_for_row(row) {
+                Some(gate) if gate < RAM_Y_ROWS => gate,
+                _ => continue,
+            };
+            let src = row * RAM_X_BYTES;
+            let dst = gate * RAM_X_BYTES;
+            self.visible[dst..dst + RAM_X_BYTES].copy_from_slice(&self.ram[src..src + RAM_X_BYTES]);
+        }
+        self.updates += 1;
+    }
+}
+
+/// Moves an address counter one step within the window between `start` and
+/// `end`, wrapping around to the other side of the window when stepping past
+/// it. Returns true on wrapping.
+fn step<T>(counter: &mut T, start: T, end: T, increment: bool) -> bool
+where
+    T: Copy + Ord + core::ops::Add<Output = T> + core::ops::Sub<Output = T> + From<u8>,
+{
+    let (low, high) = (start.min(end), start.max(end));
+
+    if increment {
+        if *counter >= high {
+            *counter = low;
+            return true;
+        }
+        *counter = *counter + T::from(1);
+    } else {
+        if *counter <= low {
+            *counter = high;
+            return true;
+        }
+        *counter = *counter - T::from(1);
+    }
+
+    false
+}
+
+/// The emulated controller and panel, and the source of the emulated pins.
+#[derive(Clone, Debug)]
+pub struct Emulator {
+    state: Rc<RefCell<State>>,
+}
+
+impl Emulator {
+    /// Creates an emulator for a panel of `x_size` by `y_size` pixels. RAM and
+    /// the panel both start white.
+    pub fn new(x_size: u16, y_size: u16) -> Emulator {
+        assert!(x_size <= MAX_X_SIZE && y_size <= MAX_Y_SIZE);
+
+        Emulator {
+            state: Rc::new(RefCell::new(State::new(x_size, y_size))),
+        }
+    }
+
+    /// Creates an emulator the size of the panel configured by `builder`,
+    /// and a screen driving it.
+    pub fn screen(
+        builder: ScreenBuilder,
+    ) -> Result<(Emulator, EmulatedScreen), ScreenError<Infallible>> {
+        builder.validate().map_err(ScreenError::ConfigError)?;
+
+        let emulator = Emulator::new(builder.x_size, builder.y_size);
+        let screen = builder.new_screen(
+            emulator.spi(),
+            emulator.dc(),
+            emulator.cs(),
+            emulator.busy(),
+            emulator.reset(),
+            &mut emulator.delay(),
+        )?;
+
+        Ok((emulator, screen))
+    }
+
+    pub fn spi(&self) -> EmuSpi {
+        EmuSpi { state: self.state.clone() }
+    }
+
+    pub fn dc(&self) -> EmuPin {
+        EmuPin { state: self.state.clone(), pin: EmuPinKind::Dc }
+    }
+
+    pub fn cs(&self) -> EmuPin {
+        EmuPin { state: self.state.clone(), pin: EmuPinKind::Cs }
+    }
+
+    pub fn reset(&self) -> EmuPin {
+        EmuPin { state: self.state.clone(), pin: EmuPinKind::Reset }
+    }
+
+    pub fn busy(&self) -> EmuBusy {
+        EmuBusy { state: self.state.clone() }
+    }
+
+    pub fn delay(&self) -> EmuDelay {
+        EmuDelay
+    }
+
+    /// Makes gate 0 the top row of the visible image rather than the bottom.
+    pub fn set_gate_zero_at_top(&self, at_top: bool) {
+        self.state.borrow_mut().gate_zero_at_top = at_top;
+    }
+
+    /// Sets the number of polls BUSY reads high for after each activation.
+    pub fn set_busy_polls(&self, polls: usize) {
+        self.state.borrow_mut().busy_polls = polls;
+    }
+
+    pub fn x_size(&self) -> u16 {
+        self.state.borrow().x_size
+    }
+
+    pub fn y_size(&self) -> u16 {
+        self.state.borrow().y_size
+    }
+
+    /// The whole of controller RAM.
+    pub fn ram(&self) -> Vec<u8> {
+        self.state.borrow().ram.clone()
+    }
+
+    /// The byte of RAM at X address `x` and Y address `y`.
+    pub fn ram_byte(&self, x: u8, y: u16) -> u8 {
+        self.state.borrow().ram[y as usize * RAM_X_BYTES + x as usize]
+    }
+
+    /// Whether the pixel at (`x`, `y`) of the visible image is white. `y` is
+    /// counted from the top.
+    pub fn pixel(&self, x: u16, y: u16) -> bool {
+        let state = self.state.borrow();
+        let byte = state.visible[state.gate(y) * RAM_X_BYTES + x as usize / 8];
+        byte & (0x80 >> (x % 8)) != 0
+    }
+
+    /// The visible panel image packed the same as images given to
+    /// `Screen::load_image`: `width_pixels_to_bytes(x_size)` bytes per row
+    /// with unused bits of the last byte set.
+    pub fn image(&self) -> Vec<u8> {
+        let state = self.state.borrow();
+        let x_bytes = width_pixels_to_bytes(state.x_size) as usize;
+        let mut image = Vec::with_capacity(x_bytes * state.y_size as usize);
+
+        for y in 0..state.y_size {
+            let gate = state.gate(y);
+            let row = &state.visible[gate * RAM_X_BYTES..gate * RAM_X_BYTES + x_bytes];
+            image.extend_from_slice(row);
+            if state.x_size % 8 != 0 {
+                *image.last_mut().unwrap() |= 0xff >> (state.x_size % 8);
+            }
+        }
+
+        image
+    }
+
+    /// Number of display updates performed.
+    pub fn updates(&self) -> usize {
+        self.state.borrow().updates
+    }
+
+    /// Whether BUSY is currently high.
+    pub fn is_busy(&self) -> bool {
+        self.state.borrow().busy > 0
+    }
+
+    /// Protocol errors seen so far, such as bytes sent while BUSY is high or
+    /// updates with the analog circuits disabled.
+    pub fn errors(&self) -> Vec<String> {
+        self.state.borrow().errors.clone()
+    }
+
+    /// Writes the visible panel image as a binary (P4) PBM.
+    pub fn write_pbm<W: Write>(&self, mut w: W) -> io::Result<()> {
+        let (x_size, y_size) = (self.x_size(), self.y_size());
+        write!(w, "P4\n{} {}\n", x_size, y_size)?;
+
+        // PBM uses 1 for black
+        let image: Vec<u8> = self.image().iter().map(|b| !b).collect();
+        w.write_all(&image)
+    }
+
+    /// Writes the visible panel image as an 8-bit grayscale PNG.
+    pub fn write_png<W: Write>(&self, mut w: W) -> io::Result<()> {
+        let (x_size, y_size) = (self.x_size() as u32, self.y_size() as u32);
+
+        let mut raw = Vec::with_capacity(((x_size + 1) * y_size) as usize);
+        for y in 0..y_size {
+            // filter type None
+            raw.push(0);
+            for x in 0..x_size {
+                raw.push(if self.pixel(x as u16, y as u16) { 0xff } else { 0x00 });
+            }
+        }
+
+        let mut ihdr = Vec::new();
+        ihdr.extend_from_slice(&x_size.to_be_bytes());
+        ihdr.extend_from_slice(&y_size.to_be_bytes());
+        // bit depth 8, grayscale, deflate, adaptive filtering, no interlace
+        ihdr.extend_from_slice(&[8, 0, 0, 0, 0]);
+
+        w.write_all(b"\x89PNG\r\n\x1a\n")?;
+        png_chunk(&mut w, b"IHDR", &ihdr)?;
+        png_chunk(&mut w, b"IDAT", &zlib_stored(&raw))?;
+        png_chunk(&mut w, b"IEND", &[])
+    }
+
+    pub fn save_pbm<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.write_pbm(BufWriter::new(File::create(path)?))
+    }
+
+    pub fn save_png<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.write_png(BufWriter::new(File::create(path)?))
+    }
+}
+
+fn png_chunk<W: Write>(w: &mut W, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    w.write_all(&(data.len() as u32).to_be_bytes())?;
+    w.write_all(kind)?;
+    w.write_all(data)?;
+
+    let crc = crc32(crc32(0xffff_ffff, kind), data) ^ 0xffff_ffff;
+    w.write_all(&crc.to_be_bytes())
+}
+
+fn crc32(mut crc: u32, data: &[u8]) -> u32 {
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xedb8_8320 } else { crc >> 1 };
+        }
+    }
+    crc
+}
+
+/// Wraps `data` in a zlib stream of uncompressed deflate blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x78, 0x01];
+
+    let mut chunks = data.chunks(0xffff).peekable();
+    if chunks.peek().is_none() {
+        out.extend_from_slice(&[0x01, 0x00, 0x00, 0xff, 0xff]);
+    }
+    while let Some(chunk) = chunks.next() {
+        let last = chunks.peek().is_none();
+        let len = chunk.len() as u16;
+        out.push(last as u8);
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&(!len).to_le_bytes());
+        out.extend_from_slice(chunk);
+    }
+
+    let (mut a, mut b) = (1u32, 0u32);
+    for byte in data {
+        a = (a + *byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    out.extend_from_slice(&((b << 16) | a).to_be_bytes());
+
+    out
+}
+
+pub struct EmuSpi {
+    state: Rc<RefCell<State>>,
+}
+
+impl FullDuplex<u8> for EmuSpi {
+    type Error = Infallible;
+
+    fn read(&mut self) -> nb::Result<u8, Infallible> {
+        let mut state = self.state.borrow_mut();
+        assert!(state.pending_reads > 0, "read without a preceding send");
+        state.pending_reads -= 1;
+        Ok(0x00)
+    }
+
+    fn send(&mut self, byte: u8) -> nb::Result<(), Infallible> {
+        let mut state = self.state.borrow_mut();
+        state.pending_reads += 1;
+        state.receive(byte);
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum EmuPinKind {
+    Dc,
+    Cs,
+    Reset,
+}
+
+pub struct EmuPin {
+    state: Rc<RefCell<State>>,
+    pin: EmuPinKind,
+}
+
+impl EmuPin {
+    fn set(&mut self, high: bool) {
+        let mut state = self.state.borrow_mut();
+        match self.pin {
+            EmuPinKind::Dc => state.dc = high,
+            EmuPinKind::Cs => state.cs = high,
+            EmuPinKind::Reset => {
+                if !high {
+                    // hardware reset also leaves deep sleep
+                    state.reset_registers();
+                    state.regs.deep_sleep = false;
+                    state.clock_enabled = false;
+                    state.analog_enabled = false;
+                    state.busy = 0;
+                }
+                state.reset = high;
+            }
+        }
+    }
+}
+
+#[allow(deprecated)]
+impl OutputPin for EmuPin {
+    fn set_low(&mut self) {
+        self.set(false);
+    }
+
+    fn set_high(&mut self) {
+        self.set(true);
+    }
+}
+
+pub struct EmuBusy {
+    state: Rc<RefCell<State>>,
+}
+
+#[allow(deprecated)]
+impl InputPin for EmuBusy {
+    fn is_high(&self) -> bool {
+        let mut state = self.state.borrow_mut();
+        if state.busy > 0 {
+            state.busy -= 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn is_low(&self) -> bool {
+        !self.is_high()
+    }
+}
+
+/// Delays return immediately.
+pub struct EmuDelay;
+
+impl DelayMs<u16> for EmuDelay {
+    fn delay_ms(&mut self, _ms: u16) {}
+}
@@ -0,0 +1,345 @@
+//! Fixed width bitmap fonts drawn on a `Canvas`, for when the `graphics`
+//! feature is not wanted.
+//!
+//! A font is a table of glyphs for a run of consecutive characters. Each
+//! glyph is `width` columns, left to right, of `(height + 7) / 8` bytes each,
+//! with the top pixel of a column in the LSB of its first byte and a set bit
+//! drawn. Characters not in the table are drawn as `?`.
+//!
+//! Text is drawn at whole multiples of the font size, set by the `scale` of
+//! a `TextStyle`. Positions may be off the canvas; text is clipped to it.
+
+use super::{Canvas, Color, Window};
+
+#[derive(Clone, Copy, Debug)]
+pub struct Font {
+    /// Width of a glyph in pixels.
+    pub width: u8,
+    /// Height of a glyph in pixels.
+    pub height: u8,
+    /// Columns left blank between glyphs.
+    pub spacing: u8,
+    /// Rows left blank between lines.
+    pub line_spacing: u8,
+    /// The character of the first glyph.
+    pub first: char,
+    pub glyphs: &'static [u8],
+}
+
+impl Font {
+    fn column_bytes(&self) -> usize {
+        (self.height as usize + 7) / 8
+    }
+
+    /// The glyph for `c`, or for `?` if there is none.
+    pub fn glyph(&self, c: char) -> Option<&'static [u8]> {
+        let glyph_bytes = self.width as usize * self.column_bytes();
+        let index = |c: char| (c as u32).checked_sub(self.first as u32).map(|i| i as usize);
+        let glyph = |i: usize| self.glyphs.get(i * glyph_bytes..(i + 1) * glyph_bytes);
+
+        index(c).and_then(glyph).or_else(|| index('?').and_then(glyph))
+    }
+
+    /// Whether pixel (`x`, `y`) of `glyph` is drawn.
+    fn is_set(&self, glyph: &[u8], x: u8, y: u8) -> bool {
+        let byte = glyph[x as usize * self.column_bytes() + y as usize / 8];
+        byte & (1 << (y % 8)) != 0
+    }
+}
+
+/// The printable ASCII characters, 5 by 7 pixels.
+pub const FONT_5X7: Font = Font {
+    width: 5,
+    height: 7,
+    spacing: 1,
+    line_spacing: 1,
+    first: ' ',
+    glyphs: &GLYPHS_5X7,
+};
+
+static GLYPHS_5X7: [u8; 95 * 5] = [
+    0x00, 0x00, 0x00, 0x00, 0x00, // ' '
+    0x00, 0x00, 0x5f, 0x00, 0x00, // !
+    0x00, 0x07, 0x00, 0x07, 0x00, // "
+    0x14, 0x7f, 0x14, 0x7f, 0x14, // #
+    0x24, 0x2a, 0x7f, 0x2a, 0x12, // $
+    0x23, 0x13, 0x08, 0x64, 0x62, // %
+    0x36, 0x49, 0x56, 0x20, 0x50, // &
+    0x00, 0x05, 0x03, 0x00, 0x00, // '
+    0x00, 0x1c, 0x22, 0x41, 0x00, // (
+    0x00, 0x41, 0x22, 0x1c, 0x00, // )
+    0x08, 0x2a, 0x1c, 0x2a, 0x08, // *
+    0x08, 0x08, 0x3e, 0x08, 0x08, // +
+    0x00, 0x50, 0x30, 0x00, 0x00, // ,
+    0x08, 0x08, 0x08, 0x08, 0x08, // -
+    0x00, 0x60, 0x60, 0x00, 0x00, // .
+    0x20, 0x10, 0x08, 0x04, 0x02, // /
+    0x3e, 0x51, 0x49, 0x45, 0x3e, // 0
+    0x00, 0x42, 0x7f, 0x40, 0x00, // 1
+    0x42, 0x61, 0x51, 0x49, 0x46, // 2
+    0x21, 0x41, 0x45, 0x4b, 0x31, // 3
+    0x18, 0x14, 0x12, 0x7f, 0x10, // 4
+    0x27, 0x45, 0x45, 0x45, 0x39, // 5
+    0x3c, 0x4a, 0x49, 0x49, 0x30, // 6
+    0x01, 0x71, 0x09, 0x05, 0x03, // 7
+    0x36, 0x49, 0x49, 0x49, 0x36, // 8
+    0x06, 0x49, 0x49, 0x29, 0x1e, // 9
+    0x00, 0x36, 0x36, 0x00, 0x00, // :
+    0x00, 0x56, 0x36, 0x00, 0x00, // ;
+    0x08, 0x14, 0x22, 0x41, 0x00, // <
+    0x14, 0x14, 0x14, 0x14, 0x14, // =
+    0x00, 0x41, 0x22, 0x14, 0x08, // >
+    0x02, 0x01, 0x51, 0x09, 0x06, // ?
+    0x32, 0x49, 0x79, 0x41, 0x3e, // @
+    0x7e, 0x11, 0x11, 0x11, 0x7e, // A
+    0x7f, 0x49, 0x49, 0x49, 0x36, // B
+    0x3e, 0x41, 0x41, 0x41, 0x22, // C
+    0x7f, 0x41, 0x41, 0x22, 0x1c, // D
+    0x7f, 0x49, 0x49, 0x49, 0x41, // E
+    0x7f, 0x09, 0x09, 0x09, 0x01, // F
+    0x3e, 0x41, 0x49, 0x49, 0x7a, // G
+    0x7f, 0x08, 0x08, 0x08, 0x7f, // H
+    0x00, 0x41, 0x7f, 0x41, 0x00, // I
+    0x20, 0x40, 0x41, 0x3f, 0x01, // J
+    0x7f, 0x08, 0x14, 0x22, 0x41, // K
+    0x7f, 0x40, 0x40, 0x40, 0x40, // L
+    0x7f, 0x02, 0x0c, 0x02, 0x7f, // M
+    0x7f, 0x04, 0x08, 0x10, 0x7f, // N
+    0x3e, 0x41, 0x41, 0x41, 0x3e, // O
+    0x7f, 0x09, 0x09, 0x09, 0x06, // P
+    0x3e, 0x41, 0x51, 0x21, 0x5e, // Q
+    0x7f, 0x09, 0x19, 0x29, 0x46, // R
+    0x46, 0x49, 0x49, 0x49, 0x31, // S
+    0x01, 0x01, 0x7f, 0x01, 0x01, // T
+    0x3f, 0x40, 0x40, 0x40, 0x3f, // U
+    0x1f, 0x20, 0x40, 0x20, 0x1f, // V
+    0x3f, 0x40, 0x38, 0x40, 0x3f, // W
+    0x63, 0x14, 0x08, 0x14, 0x63, // X
+    0x07, 0x08, 0x70, 0x08, 0x07, // Y
+    0x61, 0x51, 0x49, 0x45, 0x43, // Z
+    0x00, 0x7f, 0x41, 0x41, 0x00, // [
+    0x02, 0x04, 0x08, 0x10, 0x20, // \
+    0x00, 0x41, 0x41, 0x7f, 0x00, // ]
+    0x04, 0x02, 0x01, 0x02, 0x04, // ^
+    0x40, 0x40, 0x40, 0x40, 0x40, // _
+    0x00, 0x01, 0x02, 0x04, 0x00, // `
+    0x20, 0x54, 0x54, 0x54, 0x78, // a
+    0x7f, 0x48, 0x44, 0x44, 0x38, // b
+    0x38, 0x44, 0x44, 0x44, 0x20, // c
+    0x38, 0x44, 0x44, 0x48, 0x7f, // d
+    0x38, 0x54, 0x54, 0x54, 0x18, // e
+    0x08, 0x7e, 0x09, 0x01, 0x02, // f
+    0x0c, 0x52, 0x52, 0x52, 0x3e, // g
+    0x7f, 0x08, 0x04, 0x04, 0x78, // h
+    0x00, 0x44, 0x7d, 0x40, 0x00, // i
+    0x20, 0x40, 0x44, 0x3d, 0x00, // j
+    0x7f, 0x10, 0x28, 0x44, 0x00, // k
+    0x00, 0x41, 0x7f, 0x40, 0x00, // l
+    0x7c, 0x04, 0x18, 0x04, 0x78, // m
+    0x7c, 0x08, 0x04, 0x04, 0x78, // n
+    0x38, 0x44, 0x44, 0x44, 0x38, // o
+    0x7c, 0x14, 0x14, 0x14, 0x08, // p
+    0x08, 0x14, 0x14, 0x18, 0x7c, // q
+    0x7c, 0x08, 0x04, 0x04, 0x08, // r
+    0x48, 0x54, 0x54, 0x54, 0x20, // s
+    0x04, 0x3f, 0x44, 0x40, 0x20, // t
+    0x3c, 0x40, 0x40, 0x20, 0x7c, // u
+    0x1c, 0x20, 0x40, 0x20, 0x1c, // v
+    0x3c, 0x40, 0x30, 0x40, 0x3c, // w
+    0x44, 0x28, 0x10, 0x28, 0x44, // x
+    0x0c, 0x50, 0x50, 0x50, 0x3c, // y
+    0x44, 0x64, 0x54, 0x4c, 0x44, // z
+    0x00, 0x08, 0x36, 0x41, 0x00, // {
+    0x00, 0x00, 0x7f, 0x00, 0x00, // |
+    0x00, 0x41, 0x36, 0x08, 0x00, // }
+    0x10, 0x08, 0x08, 0x10, 0x08, // ~
+];
+
+/// Where lines are placed relative to the position or area given.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct TextStyle<'f> {
+    pub font: &'f Font,
+    /// Size of a font pixel in canvas pixels, at least 1.
+    pub scale: u8,
+    pub color: Color,
+    /// Color of the rest of each character cell, or `None` to leave it.
+    pub background: Option<Color>,
+    pub align: Align,
+}
+
+impl<'f> TextStyle<'f> {
+    /// Black text at scale 1, aligned left, with no background.
+    pub fn new(font: &'f Font) -> TextStyle<'f> {
+        TextStyle {
+            font,
+            scale: 1,
+            color: Color::Black,
+            background: None,
+            align: Align::Left,
+        }
+    }
+
+    fn scale(&self) -> i32 {
+        self.scale.max(1) as i32
+    }
+
+    /// Pixels from the start of one character to the next.
+    pub fn advance(&self) -> i32 {
+        (self.font.width as i32 + self.font.spacing as i32) * self.scale()
+    }
+
+    /// Pixels from the top of one line to the next.
+    pub fn line_height(&self) -> i32 {
+        (self.font.height as i32 + self.font.line_spacing as i32) * self.scale()
+    }
+
+    /// Width in pixels of a line of `chars` characters, not counting the
+    /// spacing after the last.
+    pub fn width_of(&self, chars: usize) -> i32 {
+        if chars == 0 {
+            return 0;
+        }
+        chars as i32 * self.advance() - self.font.spacing as i32 * self.scale()
+    }
+
+    /// Characters that fit in `width` pixels.
+    pub fn fit(&self, width: u16) -> usize {
+        let spacing = self.font.spacing as i32 * self.scale();
+        ((width as i32 + spacing) / self.advance()).max(0) as usize
+    }
+}
+
+/// Draws `text` with its first line at (`x`, `y`). Each line, split at `\n`,
+/// starts at `x` if aligned left, is centered on `x`, or ends at `x` if
+/// aligned right.
+pub fn draw_text(canvas: &mut Canvas, x: i32, y: i32, text: &str, style: &TextStyle) {
+    let clip = Window { x: 0, y: 0, w: canvas.width(), h: canvas.height() };
+    for (i, line) in text.split('\n').enumerate() {
+        let width = style.width_of(line.chars().count());
+        let x = match style.align {
+            Align::Left => x,
+            Align::Center => x - width / 2,
+            Align::Right => x - width,
+        };
+        draw_line(canvas, &clip, x, y + i as i32 * style.line_height(), line, style);
+    }
+}
+
+/// Draws `text` in `area`, wrapped at spaces to fit its width and aligned
+/// within it. Lines are split at `\n`, and words too long for a line are
+/// split where they reach the edge. Text is clipped to `area`.
+///
+/// Drawing stops at the first line that does not fit, and the length in
+/// bytes of the text drawn is returned, so the rest can be drawn elsewhere.
+pub fn draw_text_in(canvas: &mut Canvas, area: &Window, text: &str, style: &TextStyle) -> usize {
+    let max_chars = style.fit(area.w).max(1);
+    let bottom = area.y as i32 + area.h as i32;
+    let glyph_height = style.font.height as i32 * style.scale();
+
+    let mut y = area.y as i32;
+    let mut rest = text;
+    loop {
+        if y + glyph_height > bottom {
+            break;
+        }
+        let (line, next) = wrap(rest, max_chars);
+        let width = style.width_of(line.chars().count());
+        let x = area.x as i32 + match style.align {
+            Align::Left => 0,
+            Align::Center => (area.w as i32 - width) / 2,
+            Align::Right => area.w as i32 - width,
+        };
+        draw_line(canvas, area, x, y, line, style);
+
+        y += style.line_height();
+        match next {
+            Some(next) => rest = next,
+            None => return text.len(),
+        }
+    }
+
+    text.len() - rest.len()
+}
+
+/// Splits off the first line of `text` that fits in `max_chars`, and returns
+/// it with the rest, which is `None` at the end of the text.
+fn wrap(text: &str, max_chars: usize) -> (&str, Option<&str>) {
+    let line_end = text.find('\n').unwrap_or(text.len());
+    let line = &text[..line_end];
+    let rest = |i: usize| if i < text.len() { Some(&text[i..]) } else { None };
+
+    // byte index of the first character that does not fit
+    let end = match line.char_indices().nth(max_chars) {
+        Some((end, _)) => end,
+        None => return (line, rest(line_end + 1)),
+    };
+
+    let space = if line[end..].starts_with(' ') {
+        Some(end)
+    } else {
+        line[..end].rfind(' ').filter(|&i| i > 0)
+    };
+    match space {
+        Some(space) => {
+            let next = line_end - line[space..].trim_start_matches(' ').len();
+            // a line broken at its last space does not leave an empty line
+            let next = if next == line_end { line_end + 1 } else { next };
+            (line[..space].trim_end_matches(' '), rest(next))
+        }
+        None => (&line[..end], rest(end)),
+    }
+}
+
+fn draw_line(canvas: &mut Canvas, clip: &Window, x: i32, y: i32, line: &str, style: &TextStyle) {
+    let font = style.font;
+    let scale = style.scale();
+    let count = line.chars().count();
+
+    for (i, c) in line.chars().enumerate() {
+        let left = x + i as i32 * style.advance();
+        let glyph = font.glyph(c);
+        for gx in 0..font.width {
+            for gy in 0..font.height {
+                let set = glyph.map_or(false, |glyph| font.is_set(glyph, gx, gy));
+                let color = if set { Some(style.color) } else { style.background };
+                if let Some(color) = color {
+                    let px = left + gx as i32 * scale;
+                    let py = y + gy as i32 * scale;
+                    fill(canvas, clip, px, py, scale, scale, color);
+                }
+            }
+        }
+
+        if let Some(background) = style.background {
+            if i + 1 < count {
+                let spacing = font.spacing as i32 * scale;
+                let height = font.height as i32 * scale;
+                fill(canvas, clip, left + font.width as i32 * scale, y, spacing, height, background);
+            }
+        }
+    }
+}
+
+/// Fills a rectangle clipped to `clip`.
+fn fill(canvas: &mut Canvas, clip: &Window, x: i32, y: i32, w: i32, h: i32, color: Color) {
+    let x_start = x.max(clip.x as i32);
+    let y_start = y.max(clip.y as i32);
+    let x_end = (x + w).min(clip.x as i32 + clip.w as i32);
+    let y_end = (y + h).min(clip.y as i32 + clip.h as i32);
+    if x_start >= x_end || y_start >= y_end {
+        return;
+    }
+
+    canvas.fill_rect(
+        x_start as u16, y_start as u16,
+        (x_end - x_start) as u16, (y_end - y_start) as u16,
+        color,
+    );
+}
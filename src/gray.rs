@@ -0,0 +1,100 @@
+//! 4 level grayscale images, shown one level at a time.
+//!
+//! The controller only holds one bit per pixel, so `Screen::show_gray_image`
+//! first shows the black pixels with a full update, which leaves every other
+//! pixel white. Each gray level is then written as its own `GrayPlane`, black
+//! only where the image has that level, and shown with a partial update using
+//! a LUT given for that level. `gray_lut` builds one that drives the black
+//! pixels of the plane towards black for a number of frames and leaves the
+//! rest alone, so the gray pixels stop partway from white.
+//!
+//! The drive voltage is the VSL used to move pixels to black by the preset
+//! partial update LUTs, such as `LUT_PART_CFAP200200A0_154`. No frame counts
+//! are given for the presets, as the panel vendor does not publish gray
+//! waveforms and the counts giving distinct grays vary with the panel and
+//! temperature. They have to be found by trying them on the panel in use.
+
+use super::{width_pixels_to_bytes, ImageSource, Phase, Voltage, Waveform, WaveformError, LUT_LEN};
+
+/// A LUT for one level of `Screen::show_gray_image`, a single phase driving
+/// pixels whose new bit is 0 to VSL for `frames` frames, whatever their old
+/// bit, and every other pixel to VSS. Darker levels take more frames.
+///
+/// Returns `WaveformError::FramesOutOfRange` if `frames` is greater than
+/// `MAX_FRAMES`.
+pub fn gray_lut(frames: u8) -> Result<[u8; LUT_LEN], WaveformError> {
+    let mut waveform = Waveform::default();
+    waveform.phases[0] = Phase {
+        from_0_to_0: Voltage::Vsl,
+        from_0_to_1: Voltage::Vss,
+        from_1_to_0: Voltage::Vsl,
+        from_1_to_1: Voltage::Vss,
+        frames,
+    };
+    waveform.encode()
+}
+
+/// Bytes in a row of `x` pixels of a 4 level grayscale image.
+pub const fn gray_width_pixels_to_bytes(x: u16) -> usize {
+    // round up when converted from pixels to bytes
+    (x as usize + 3) >> 2
+}
+
+/// Length in bytes of a 4 level grayscale image of `x` by `y` pixels.
+///
+/// Grayscale images are packed 4 pixels per byte, leftmost pixel in the top
+/// two bits, with levels from 0, black, to 3, white.
+pub const fn gray_image_len(x: u16, y: u16) -> usize {
+    gray_width_pixels_to_bytes(x) * y as usize
+}
+
+/// The pixels of a grayscale image at one level, packed the same as images
+/// given to `Screen::load_image`: black where the image has that level and
+/// white everywhere else.
+#[derive(Clone, Copy, Debug)]
+pub struct GrayPlane<'a> {
+    image: &'a [u8],
+    width: u16,
+    level: u8,
+}
+
+impl<'a> GrayPlane<'a> {
+    /// The pixels of `image`, `width` pixels wide, at `level`, from 0 to 3.
+    pub fn level(image: &'a [u8], width: u16, level: u8) -> GrayPlane<'a> {
+        GrayPlane { image, width, level }
+    }
+
+    fn rows(&self) -> usize {
+        self.image.len() / gray_width_pixels_to_bytes(self.width).max(1)
+    }
+}
+
+impl<'a> ImageSource for GrayPlane<'a> {
+    fn len(&self) -> usize {
+        width_pixels_to_bytes(self.width) as usize * self.rows()
+    }
+
+    fn stream<E, F>(&mut self, mut sink: F) -> Result<(), E>
+    where
+        F: FnMut(u8) -> Result<(), E>,
+    {
+        let row_bytes = gray_width_pixels_to_bytes(self.width);
+        for row in self.image.chunks(row_bytes).take(self.rows()) {
+            for x_byte in 0..width_pixels_to_bytes(self.width) as usize {
+                let mut b = 0xff;
+                for bit in 0..8 {
+                    let x = x_byte * 8 + bit;
+                    if x >= self.width as usize {
+                        break;
+                    }
+                    let level = row[x / 4] >> (6 - 2 * (x % 4)) & 0b11;
+                    if level == self.level {
+                        b &= !(0x80 >> bit);
+                    }
+                }
+                sink(b)?;
+            }
+        }
+        Ok(())
+    }
+}
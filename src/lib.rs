@@ -13,23 +13,91 @@ extern crate embedded_hal;
 extern crate nb;
 extern crate volatile_register;
 
+#[cfg(feature = "graphics")]
+extern crate embedded_graphics_core;
+#[cfg(feature = "macros")]
+extern crate cfap_screen_macros;
+
+#[cfg(feature = "std")]
+#[macro_use]
+extern crate std;
+
+use core::convert::TryFrom;
+
 use embedded_hal::blocking::delay::DelayMs;
+#[allow(deprecated)]
 use embedded_hal::digital::{OutputPin, InputPin};
 use embedded_hal::spi::FullDuplex;
 
-// TODO: const fn
-pub fn width_pixels_to_bytes(x: u16) -> u8 {
+use params::{Parameters, UpdateSequence};
+
+pub mod bitbang;
+pub mod compress;
+pub mod decode;
+pub mod dither;
+pub mod font;
+pub mod params;
+pub mod qr;
+#[cfg(feature = "emulator")]
+pub mod emulator;
+#[cfg(feature = "mock")]
+pub mod mock;
+mod band;
+mod canvas;
+mod console;
+mod gray;
+mod plan;
+mod profile;
+mod refresh;
+mod shadow;
+mod size;
+mod source;
+mod waveform;
+
+pub use band::Bands;
+#[cfg(feature = "macros")]
+pub use cfap_screen_macros::include_panel_image;
+pub use canvas::{Canvas, Color};
+pub use console::Console;
+pub use gray::{gray_image_len, gray_lut, gray_width_pixels_to_bytes, GrayPlane};
+pub use plan::{coalesce, Plan, PlanPolicy, Window};
+pub use profile::{ProfileError, PROFILE_LEN, PROFILE_MAGIC, PROFILE_VERSION};
+pub use refresh::{Refresh, RefreshPolicy, RefreshTracker};
+pub use shadow::Shadow;
+pub use size::{
+    CFAP200200A0_154, CFAP200200A0_154Size, CFAP200200A1_154, CFAP200200A1_154Size,
+    Dimensions, DynamicSize, FixedSize, SizedPreset,
+};
+pub use source::{from_fn, FromFn, ImageSource, Rows, SubImage};
+pub use waveform::{
+    Phase, Voltage, Waveform, WaveformError, LUT_LEN, MAX_FRAMES, PHASES,
+};
+
+pub const fn width_pixels_to_bytes(x: u16) -> u8 {
     // round up when converted from pixels to bytes
     ((x + 7) >> 3) as u8
 }
 
+/// Length in bytes of a full screen image of `x` by `y` pixels.
+pub const fn image_len(x: u16, y: u16) -> usize {
+    width_pixels_to_bytes(x) as usize * y as usize
+}
+
+/// Largest width in pixels addressable by the 5-bit X RAM address.
+pub const MAX_X_SIZE: u16 = 256;
+/// Number of gates driven by the controller, G0 to G299.
+pub const MAX_Y_SIZE: u16 = 300;
+/// Largest value of the 7-bit dummy line period.
+pub const MAX_DUMMY_LINE: u8 = 0x7f;
+/// Largest value of the 4-bit gate line width.
+pub const MAX_GATE_LINE: u8 = 0x0f;
+
 pub enum Preset {
     CFAP200200A0_154,
     CFAP200200A1_154,
 }
 
 pub struct ScreenBuilder {
-    // TODO: const generics
     pub x_size: u16,
     pub y_size: u16,
 
@@ -38,6 +106,7 @@ pub struct ScreenBuilder {
     pub dummy_line: u8,
     pub gate_line: u8,
     pub entry_mode: EntryMode,
+    pub gate_scan: GateScan,
 
     pub lut_full: [u8; 30],
     pub lut_part: [u8; 30],
@@ -55,6 +124,7 @@ impl ScreenBuilder {
                 dummy_line: DUMMY_LINE_CFAP200200A0_154,
                 gate_line: GATE_LINE_CFAP200200A0_154,
                 entry_mode: EntryMode::XIncrementYDecrement,
+                gate_scan: GateScan::POR,
 
                 lut_full: LUT_FULL_CFAP200200A0_154,
                 lut_part: LUT_PART_CFAP200200A0_154,
@@ -68,6 +138,7 @@ impl ScreenBuilder {
                 dummy_line: DUMMY_LINE_CFAP200200A1_154,
                 gate_line: GATE_LINE_CFAP200200A1_154,
                 entry_mode: EntryMode::XIncrementYDecrement,
+                gate_scan: GateScan::POR,
 
                 lut_full: LUT_FULL_CFAP200200A1_154,
                 lut_part: LUT_PART_CFAP200200A1_154,
@@ -75,6 +146,77 @@ impl ScreenBuilder {
         }
     }
 
+    /// Width in pixels.
+    pub fn x_size(mut self, x_size: u16) -> ScreenBuilder {
+        self.x_size = x_size;
+        self
+    }
+
+    /// Height in pixels. This is also the number of gates used.
+    pub fn y_size(mut self, y_size: u16) -> ScreenBuilder {
+        self.y_size = y_size;
+        self
+    }
+
+    pub fn soft_start(mut self, soft_start: [u8; 3]) -> ScreenBuilder {
+        self.soft_start = soft_start;
+        self
+    }
+
+    /// The VCOM register, A[7:0] of command 0x2C ("Write VCOM register" in the
+    /// SSD1608 datasheet). The whole byte is the register, so any value is
+    /// accepted.
+    pub fn vcom(mut self, vcom: u8) -> ScreenBuilder {
+        self.vcom = vcom;
+        self
+    }
+
+    pub fn dummy_line(mut self, dummy_line: u8) -> ScreenBuilder {
+        self.dummy_line = dummy_line;
+        self
+    }
+
+    pub fn gate_line(mut self, gate_line: u8) -> ScreenBuilder {
+        self.gate_line = gate_line;
+        self
+    }
+
+    pub fn entry_mode(mut self, entry_mode: EntryMode) -> ScreenBuilder {
+        self.entry_mode = entry_mode;
+        self
+    }
+
+    pub fn gate_scan(mut self, gate_scan: GateScan) -> ScreenBuilder {
+        self.gate_scan = gate_scan;
+        self
+    }
+
+    pub fn lut_full(mut self, lut_full: [u8; 30]) -> ScreenBuilder {
+        self.lut_full = lut_full;
+        self
+    }
+
+    pub fn lut_part(mut self, lut_part: [u8; 30]) -> ScreenBuilder {
+        self.lut_part = lut_part;
+        self
+    }
+
+    /// Checks the configuration against the limits of the controller. Every
+    /// way of creating a `Screen` runs this first, so settings written to the
+    /// fields directly are checked the same as those given to the setters.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        validate_size(self.x_size, self.y_size)?;
+        if self.dummy_line > MAX_DUMMY_LINE {
+            return Err(ConfigError::DummyLine);
+        }
+        if self.gate_line > MAX_GATE_LINE {
+            return Err(ConfigError::GateLine);
+        }
+
+        Ok(())
+    }
+
+    #[allow(deprecated, clippy::type_complexity)]
     pub fn new_screen<SPI, DC, CS, BUSY, RST, ERR, DELAY>(
         self,
         serial: SPI,
@@ -83,15 +225,63 @@ impl ScreenBuilder {
         busy: BUSY,
         reset: RST,
         delay: &mut DELAY,
-    ) -> Result<Screen<SPI, DC, CS, BUSY, RST, ERR>, ERR>
+    ) -> Result<Screen<SPI, DC, CS, BUSY, RST, ERR>, ScreenError<ERR>>
+    where
+        SPI: FullDuplex<u8, Error = ERR>,
+        DC: OutputPin,
+        CS: OutputPin,
+        BUSY: InputPin,
+        RST: OutputPin,
+        DELAY: DelayMs<u16>,
+    {
+        let size = DynamicSize {
+            x_size: self.x_size,
+            y_size: self.y_size,
+        };
+
+        Screen::new(
+            serial,
+            dc,
+            cs,
+            busy,
+            reset,
+            self,
+            size,
+            delay,
+        )
+    }
+
+    /// Creates a screen whose dimensions are given by `size`. With a
+    /// `FixedSize` the length of full screen images is checked at compile
+    /// time. `SizedPreset::builder` gives a builder to match.
+    ///
+    /// The configuration is validated the same as with `new_screen`, and
+    /// `x_size` and `y_size` must equal those of `size` or
+    /// `ScreenError::BoundsError` is returned.
+    #[allow(deprecated, clippy::too_many_arguments, clippy::type_complexity)]
+    pub fn new_sized_screen<SPI, DC, CS, BUSY, RST, ERR, D, DELAY>(
+        self,
+        size: D,
+        serial: SPI,
+        dc: DC,
+        cs: CS,
+        busy: BUSY,
+        reset: RST,
+        delay: &mut DELAY,
+    ) -> Result<Screen<SPI, DC, CS, BUSY, RST, ERR, D>, ScreenError<ERR>>
     where
         SPI: FullDuplex<u8, Error = ERR>,
         DC: OutputPin,
         CS: OutputPin,
         BUSY: InputPin,
         RST: OutputPin,
+        D: Dimensions,
         DELAY: DelayMs<u16>,
     {
+        if (self.x_size, self.y_size) != (size.x_size(), size.y_size()) {
+            return Err(ScreenError::BoundsError);
+        }
+
         Screen::new(
             serial,
             dc,
@@ -99,11 +289,24 @@ impl ScreenBuilder {
             busy,
             reset,
             self,
+            size,
             delay,
         )
     }
 }
 
+fn validate_size(x_size: u16, y_size: u16) -> Result<(), ConfigError> {
+    if x_size == 0 || x_size > MAX_X_SIZE {
+        return Err(ConfigError::XSize);
+    }
+    if y_size == 0 || y_size > MAX_Y_SIZE {
+        return Err(ConfigError::YSize);
+    }
+
+    Ok(())
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum EntryMode {
     XDecrementYDecrement = 0b00,
     XIncrementYDecrement = 0b01,
@@ -111,6 +314,101 @@ pub enum EntryMode {
     XIncrementYIncrement = 0b11,
 }
 
+impl EntryMode {
+    fn from_bits(bits: u8) -> Option<EntryMode> {
+        match bits {
+            0b00 => Some(EntryMode::XDecrementYDecrement),
+            0b01 => Some(EntryMode::XIncrementYDecrement),
+            0b10 => Some(EntryMode::XDecrementYIncrement),
+            0b11 => Some(EntryMode::XIncrementYIncrement),
+            _ => None,
+        }
+    }
+
+    fn flip_y(self) -> EntryMode {
+        match self {
+            EntryMode::XDecrementYDecrement => EntryMode::XDecrementYIncrement,
+            EntryMode::XIncrementYDecrement => EntryMode::XIncrementYIncrement,
+            EntryMode::XDecrementYIncrement => EntryMode::XDecrementYDecrement,
+            EntryMode::XIncrementYIncrement => EntryMode::XIncrementYDecrement,
+        }
+    }
+}
+
+/// GD, the first output gate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FirstGate {
+    /// G0, G1, G2, G3...
+    G0 = 0,
+    /// G1, G0, G3, G2...
+    G1 = 1,
+}
+
+/// SM, the scanning order of the left and right gates.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GateOrder {
+    /// G0, G1, G2...G299
+    Interlaced = 0,
+    /// G0, G2, G4...G298, G1, G3...G299
+    Separated = 1,
+}
+
+/// TB, the direction the gates are scanned in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScanDirection {
+    /// G0 to G299
+    Forward = 0,
+    /// G299 to G0
+    Reverse = 1,
+}
+
+/// The gate scanning options of `Command::DriverOutputControl`.
+///
+/// When the scan direction is reversed the Y RAM addresses given to
+/// `Screen::set_display_area` are mirrored, and the Y direction of the entry
+/// mode flipped to match, so images are written in the same orientation as
+/// with a forward scan. The first gate and gate order only change how RAM
+/// rows are wired to gates and do not affect addressing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GateScan {
+    pub first_gate: FirstGate,
+    pub order: GateOrder,
+    pub direction: ScanDirection,
+}
+
+impl GateScan {
+    /// The power on reset gate scanning options.
+    pub const POR: GateScan = GateScan {
+        first_gate: FirstGate::G0,
+        order: GateOrder::Interlaced,
+        direction: ScanDirection::Forward,
+    };
+
+    /// B[2:0] of `Command::DriverOutputControl`.
+    pub fn bits(&self) -> u8 {
+        (self.first_gate as u8) << 2 | (self.order as u8) << 1 | self.direction as u8
+    }
+
+    /// The inverse of `bits`. Returns `None` if any bit above B[2] is set.
+    pub fn from_bits(bits: u8) -> Option<GateScan> {
+        if bits & !0b111 != 0 {
+            return None;
+        }
+
+        Some(GateScan {
+            first_gate: if bits & 0b100 != 0 { FirstGate::G1 } else { FirstGate::G0 },
+            order: if bits & 0b010 != 0 { GateOrder::Separated } else { GateOrder::Interlaced },
+            direction: if bits & 0b001 != 0 { ScanDirection::Reverse } else { ScanDirection::Forward },
+        })
+    }
+}
+
+impl Default for GateScan {
+    fn default() -> GateScan {
+        GateScan::POR
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 pub enum Command {
     /// Data: A[7:0], {[0; 7], A[8]}, {[0;5], B[2:0]}
@@ -346,6 +644,48 @@ pub enum Command {
     Nop = 0xff,
 }
 
+impl From<Command> for u8 {
+    fn from(cmd: Command) -> u8 {
+        cmd as u8
+    }
+}
+
+/// An opcode that is not a `Command`. It may still be sent with
+/// `Screen::write_raw`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UnknownOpcode(pub u8);
+
+impl TryFrom<u8> for Command {
+    type Error = UnknownOpcode;
+
+    fn try_from(opcode: u8) -> Result<Command, UnknownOpcode> {
+        Ok(match opcode {
+            0x01 => Command::DriverOutputControl,
+            0x0c => Command::BoosterSoftStartControl,
+            0x0f => Command::GateScanStartPosition,
+            0x10 => Command::DeepSleepMode,
+            0x11 => Command::DataEntryModeSetting,
+            0x12 => Command::SwReset,
+            0x1a => Command::TemperatureSensorControl,
+            0x20 => Command::MasterActivation,
+            0x21 => Command::DisplayUpdateControl1,
+            0x22 => Command::DisplayUpdateControl2,
+            0x24 => Command::WriteRam,
+            0x2c => Command::WriteVcomRegister,
+            0x32 => Command::WriteLutRegister,
+            0x3a => Command::SetDummyLinePeriod,
+            0x3b => Command::SetGateLineWidth,
+            0x3c => Command::BorderWaveformControl,
+            0x44 => Command::SetRamXAddressStartEndPosition,
+            0x45 => Command::SetRamYAddressStartEndPosition,
+            0x4e => Command::SetRamXAddressCounter,
+            0x4f => Command::SetRamYAddressCounter,
+            0xff => Command::Nop,
+            _ => return Err(UnknownOpcode(opcode)),
+        })
+    }
+}
+
 pub const SOFT_START_CFAP200200A0_154: [u8; 3] = [0xd7, 0xd6, 0x9d];
 pub const SOFT_START_CFAP200200A1_154: [u8; 3] = [0xd7, 0xd6, 0x9d];
 
@@ -490,10 +830,24 @@ pub const LUT_PART_CFAP200200A1_154: [u8; 30] = [
     0x00,
 ];
 
+/// A `ScreenBuilder` setting outside of the limits of the controller.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConfigError {
+    /// `x_size` is 0 or greater than `MAX_X_SIZE`.
+    XSize,
+    /// `y_size` is 0 or greater than `MAX_Y_SIZE`.
+    YSize,
+    /// `dummy_line` is greater than `MAX_DUMMY_LINE`.
+    DummyLine,
+    /// `gate_line` is greater than `MAX_GATE_LINE`.
+    GateLine,
+}
+
 #[derive(Debug)]
 pub enum ScreenError<ERR> {
     BoundsError,
     LengthError,
+    ConfigError(ConfigError),
     SpiError(ERR)
 }
 
@@ -508,6 +862,7 @@ impl<ERR> Clone for ScreenError<ERR> where ERR: Clone {
         match self {
             ScreenError::BoundsError => ScreenError::BoundsError,
             ScreenError::LengthError => ScreenError::LengthError,
+            ScreenError::ConfigError(err) => ScreenError::ConfigError(*err),
             ScreenError::SpiError(err) => ScreenError::SpiError(err.clone()),
         }
     }
@@ -515,7 +870,8 @@ impl<ERR> Clone for ScreenError<ERR> where ERR: Clone {
 
 impl<ERR> Copy for ScreenError<ERR> where ERR: Copy {}
 
-pub struct Screen<SPI, DC, CS, BUSY, RST, ERR>
+#[allow(deprecated)]
+pub struct Screen<SPI, DC, CS, BUSY, RST, ERR, D = DynamicSize>
 where
     SPI: FullDuplex<u8, Error = ERR>,
     DC: OutputPin,
@@ -530,21 +886,24 @@ where
     busy: BUSY,
     reset: RST,
 
-    // TODO: const generics
-    x_size: u16,
-    y_size: u16,
+    size: D,
+    gate_scan: GateScan,
+    entry_mode: EntryMode,
     lut_full: [u8; 30],
     lut_part: [u8; 30],
 }
 
-impl<SPI, DC, CS, BUSY, RST, ERR> Screen<SPI, DC, CS, BUSY, RST, ERR>
+#[allow(deprecated)]
+impl<SPI, DC, CS, BUSY, RST, ERR, D> Screen<SPI, DC, CS, BUSY, RST, ERR, D>
 where
     SPI: FullDuplex<u8, Error = ERR>,
     DC: OutputPin,
     CS: OutputPin,
     BUSY: InputPin,
     RST: OutputPin,
+    D: Dimensions,
 {
+    #[allow(clippy::too_many_arguments)]
     fn new<DELAY>(
         serial: SPI,
         dc: DC,
@@ -552,19 +911,23 @@ where
         busy: BUSY,
         reset: RST,
         builder: ScreenBuilder,
+        size: D,
         delay: &mut DELAY,
-    ) -> Result<Screen<SPI, DC, CS, BUSY, RST, ERR>, ERR>
+    ) -> Result<Self, ScreenError<ERR>>
     where
         DELAY: DelayMs<u16>,
     {
+        builder.validate().map_err(ScreenError::ConfigError)?;
+
         let mut screen = Screen {
             serial,
             dc,
             cs,
             busy,
             reset,
-            x_size: builder.x_size,
-            y_size: builder.y_size,
+            size,
+            gate_scan: builder.gate_scan,
+            entry_mode: builder.entry_mode,
             lut_full: builder.lut_full,
             lut_part: builder.lut_part
         };
@@ -579,41 +942,55 @@ where
         delay.delay_ms(10);
 
         // Panel configuration, Gate selection
-        screen.write_cmd_string(
-            Command::DriverOutputControl,
-            &[builder.x_size as u8, ((builder.x_size >> 8) & 0x01) as u8, 0x00]
-        )?;
-        screen.write_cmd_string(
-            Command::BoosterSoftStartControl,
-            &builder.soft_start
-        )?;
+        let gates = screen.size.y_size();
+        screen.write_params(&params::DriverOutput {
+            gates,
+            gate_scan: builder.gate_scan,
+        })?;
+        screen.write_params(&params::SoftStart {
+            phase1: builder.soft_start[0],
+            phase2: builder.soft_start[1],
+            phase3: builder.soft_start[2],
+        })?;
         // VCOM setting
-        screen.write_cmd_string(
-            Command::WriteVcomRegister,
-            &[builder.vcom]
-        )?;
+        screen.write_params(&params::Vcom { value: builder.vcom })?;
         //dummy line per gate
-        screen.write_cmd_string(
-            Command::SetDummyLinePeriod,
-            &[builder.dummy_line]
-        )?;
+        screen.write_params(&params::DummyLinePeriod { period: builder.dummy_line })?;
         // Gate time setting
-        screen.write_cmd_string(
-            Command::SetGateLineWidth,
-            &[builder.gate_line]
-        )?;
-        // X increase, Y decrease
-        screen.write_cmd_string(
-            Command::DataEntryModeSetting,
-            &[builder.entry_mode as u8]
-        )?;
+        screen.write_params(&params::GateLineWidth { width: builder.gate_line })?;
+        // Data entry mode
+        let mut entry_mode = builder.entry_mode;
+        if builder.gate_scan.direction == ScanDirection::Reverse {
+            // RAM Y addresses are mirrored, so the Y direction is as well
+            entry_mode = entry_mode.flip_y();
+        }
+        screen.write_params(&params::DataEntryMode {
+            id: entry_mode,
+            am: params::AddressDirection::X,
+        })?;
 
         Ok(screen)
     }
 
-    pub fn show_full_screen_image(&mut self, image: &[u8]) -> Result<(), ScreenError<ERR>> {
-        let x_size = ((self.x_size + 7) >> 3) as u8;
-        let y_size = self.y_size;
+    pub fn x_size(&self) -> u16 {
+        self.size.x_size()
+    }
+
+    pub fn y_size(&self) -> u16 {
+        self.size.y_size()
+    }
+
+    /// With a `FixedSize` screen `image` is a `[u8; N]` and can never be the
+    /// wrong length.
+    pub fn show_full_screen_image(&mut self, image: &D::Image) -> Result<(), ScreenError<ERR>> {
+        self.show_full_screen_source(image.as_ref())
+    }
+
+    /// Like `show_full_screen_image`, but the image is streamed from `image`
+    /// and its length checked at runtime.
+    pub fn show_full_screen_source<I: ImageSource>(&mut self, image: I) -> Result<(), ScreenError<ERR>> {
+        let x_size = width_pixels_to_bytes(self.size.x_size());
+        let y_size = self.size.y_size();
 
         if x_size as usize * y_size as usize != image.len() {
             return Err(ScreenError::LengthError);
@@ -635,41 +1012,343 @@ where
         Ok(())
     }
 
+    /// Shows a full screen image drawn a band at a time into `buf`, which must
+    /// hold at least one row. See `Bands`.
+    pub fn render_full<F: FnMut(&mut Canvas)>(&mut self, buf: &mut [u8], draw: F) -> Result<(), ScreenError<ERR>> {
+        let bands = Bands::new(buf, self.size.x_size(), self.size.y_size(), draw)
+            .ok_or(ScreenError::LengthError)?;
+
+        self.show_full_screen_source(bands)
+    }
+
+    /// Like `render_full`, but the screen is updated with the partial update
+    /// LUT.
+    pub fn render_partial<F: FnMut(&mut Canvas)>(&mut self, buf: &mut [u8], draw: F) -> Result<(), ScreenError<ERR>> {
+        let x_size = width_pixels_to_bytes(self.size.x_size());
+        let y_size = self.size.y_size();
+        let bands = Bands::new(buf, self.size.x_size(), y_size, draw)
+            .ok_or(ScreenError::LengthError)?;
+
+        self.load_partial_update_lut()?;
+        self.power_on()?;
+
+        self.set_display_area(
+            0, x_size - 1,
+            y_size - 1, 0
+        )?;
+
+        self.load_image(bands)?;
+        self.update_partial()?;
+
+        self.power_off()?;
+
+        Ok(())
+    }
+
+    /// Shows a 4 level grayscale image, packed as described by
+    /// `gray_image_len`.
+    ///
+    /// Level 0 is shown with a full update, leaving every other level white.
+    /// Levels 1 and 2 are then each written as a `GrayPlane` and shown with a
+    /// partial update using their LUT from `lut_levels`, which should move
+    /// only the pixels at that level towards black. `gray_lut` builds such
+    /// LUTs from frame counts chosen for the panel in use.
+    ///
+    /// Afterwards RAM holds level 2 and the LUT register the level 2 LUT, so
+    /// the next update must be a full refresh. Callers tracking
+    /// refreshes with a `RefreshTracker` must call
+    /// `RefreshTracker::force_full` after this.
+    pub fn show_gray_image(
+        &mut self,
+        image: &[u8],
+        lut_levels: &[[u8; LUT_LEN]; 2],
+    ) -> Result<(), ScreenError<ERR>> {
+        let width = self.size.x_size();
+        let x_size = width_pixels_to_bytes(width);
+        let y_size = self.size.y_size();
+
+        if gray_image_len(width, y_size) != image.len() {
+            return Err(ScreenError::LengthError);
+        }
+
+        self.load_full_update_lut()?;
+        self.power_on()?;
+        self.set_display_area(
+            0, x_size - 1,
+            y_size - 1, 0
+        )?;
+        self.load_image(GrayPlane::level(image, width, 0))?;
+        self.update_full()?;
+
+        for (level, lut) in (1..).zip(lut_levels) {
+            self.write_params(&params::Lut(*lut))?;
+            self.power_on()?;
+            self.set_display_area(
+                0, x_size - 1,
+                y_size - 1, 0
+            )?;
+            self.load_image(GrayPlane::level(image, width, level))?;
+            self.update_partial()?;
+        }
+
+        self.power_off()?;
+
+        Ok(())
+    }
+
+    /// Writes an image as with `load_partial_image`, shows it with a partial
+    /// update, then writes it again.
+    ///
+    /// A partial update leaves RAM out of step with the image the controller
+    /// compares the next update against, which shows as artefacts in that
+    /// update. Writing the image again, as the vendor sample code does, puts
+    /// them back in step. `image` is streamed twice.
+    pub fn show_partial_image<I: ImageSource>(
+        &mut self,
+        x_start: u8, x_size: u8,
+        y_start: u16, y_size: u16,
+        mut image: I,
+    ) -> Result<(), ScreenError<ERR>> {
+        self.load_partial_image(x_start, x_size, y_start, y_size, &mut image)?;
+        if x_size == 0 || y_size == 0 {
+            return Ok(());
+        }
+
+        self.load_partial_update_lut()?;
+        self.power_on()?;
+        self.update_partial()?;
+        self.power_off()?;
+
+        self.load_partial_image(x_start, x_size, y_start, y_size, &mut image)
+    }
+
     /// `x_start` and `x_size` are in bytes. `y_start` and `y_size` are in pixels.
-    /// `y_start` must be greater than or equal to `y_size`.
-    pub fn load_partial_image(
+    /// Nothing is written for an empty region.
+    pub fn load_partial_image<I: ImageSource>(
         &mut self,
         x_start: u8, x_size: u8,
         y_start: u16, y_size: u16,
-        image: &[u8],
+        image: I,
     ) -> Result<(), ScreenError<ERR>> {
         if x_size as usize * y_size as usize != image.len() {
             return Err(ScreenError::LengthError);
         }
+        if x_size == 0 || y_size == 0 {
+            return Ok(());
+        }
 
-        self.set_display_area(
-            x_start, x_start + x_size - 1,
-            y_start, y_start + (y_size - 1),
-        )?;
+        let x_end = x_start.checked_add(x_size - 1).ok_or(ScreenError::BoundsError)?;
+        let y_end = y_start.checked_add(y_size - 1).ok_or(ScreenError::BoundsError)?;
+        self.set_display_area(x_start, x_end, y_start, y_end)?;
         self.load_image(image)?;
 
         Ok(())
     }
 
+    /// Writes `image`, `w` by `h` pixels packed with rows starting on a byte,
+    /// with its top left at pixel (`x`, `y`) from the top left of the screen.
+    ///
+    /// The bytes enclosing the pixels are sent from `shadow` with the pixels
+    /// merged in, so pixels sharing those bytes are left as they were. The
+    /// pixels are merged into `shadow` once they have been written, so it is
+    /// left unchanged on error.
+    pub fn load_pixels(
+        &mut self,
+        shadow: &mut Shadow,
+        x: u16, y: u16,
+        w: u16, h: u16,
+        image: &[u8],
+    ) -> Result<(), ScreenError<ERR>> {
+        let width = self.size.x_size();
+        let height = self.size.y_size();
+
+        if shadow.width() != width || shadow.height() != height {
+            return Err(ScreenError::LengthError);
+        }
+        if image_len(w, h) != image.len() {
+            return Err(ScreenError::LengthError);
+        }
+        if x as u32 + w as u32 > width as u32 || y as u32 + h as u32 > height as u32 {
+            return Err(ScreenError::BoundsError);
+        }
+        if w == 0 || h == 0 {
+            return Ok(());
+        }
+
+        let window = Window { x, y, w, h };
+        self.set_window_area(&window)?;
+        self.load_image(shadow.merged(&window, image))?;
+        shadow.merge(&window, image);
+
+        Ok(())
+    }
+
+    /// Writes the bytes of `image`, a full screen image, enclosing `window`,
+    /// which must be within the screen and not empty.
+    fn load_window(&mut self, image: &[u8], window: &Window) -> Result<(), ScreenError<ERR>> {
+        let x_start = window.x / 8;
+        let x_end = (window.x + window.w - 1) / 8;
+        let source = SubImage::new(
+            image,
+            width_pixels_to_bytes(self.size.x_size()) as usize,
+            x_start as usize, window.y as usize,
+            (x_end - x_start + 1) as usize, window.h as usize,
+        ).ok_or(ScreenError::BoundsError)?;
+
+        self.set_window_area(window)?;
+        self.load_image(source)?;
+
+        Ok(())
+    }
+
+    /// Sets the display area to the bytes enclosing `window`, which must be
+    /// within the screen and not empty, so that they are written in the
+    /// order of an image: rows from the top, left to right.
+    ///
+    /// Where the entry mode decrements an address, RAM counts up from the
+    /// other side of the screen, as it does for the full screen images
+    /// written by the presets, which decrement Y.
+    fn set_window_area(&mut self, window: &Window) -> Result<(), ScreenError<ERR>> {
+        let x_bytes = width_pixels_to_bytes(self.size.x_size());
+        let height = self.size.y_size();
+        let Window { x, y, w, h } = *window;
+
+        let x_start = (x / 8) as u8;
+        let x_end = ((x + w - 1) / 8) as u8;
+        let (x_start, x_end) = match self.entry_mode {
+            EntryMode::XIncrementYDecrement | EntryMode::XIncrementYIncrement => (x_start, x_end),
+            EntryMode::XDecrementYDecrement | EntryMode::XDecrementYIncrement => {
+                (x_bytes - 1 - x_start, x_bytes - 1 - x_end)
+            }
+        };
+        let (y_start, y_end) = match self.entry_mode {
+            EntryMode::XDecrementYIncrement | EntryMode::XIncrementYIncrement => (y, y + h - 1),
+            EntryMode::XDecrementYDecrement | EntryMode::XIncrementYDecrement => {
+                (height - 1 - y, height - y - h)
+            }
+        };
+
+        self.set_display_area(
+            x_start, x_end,
+            y_start, y_end
+        )
+    }
+
+    /// `load_pixels`, then a partial update, then the pixels are written
+    /// again as with `show_partial_image`.
+    pub fn update_pixels(
+        &mut self,
+        shadow: &mut Shadow,
+        x: u16, y: u16,
+        w: u16, h: u16,
+        image: &[u8],
+    ) -> Result<(), ScreenError<ERR>> {
+        self.load_pixels(shadow, x, y, w, h, image)?;
+        if w == 0 || h == 0 {
+            return Ok(());
+        }
+
+        self.load_partial_update_lut()?;
+        self.power_on()?;
+        self.update_partial()?;
+        self.power_off()?;
+
+        self.load_window(shadow.as_bytes(), &Window { x, y, w, h })
+    }
+
+    /// Shows `image`, a full screen image, as planned by `PlanPolicy::plan`
+    /// from the image on the screen.
+    pub fn show_plan(&mut self, plan: &Plan, image: &[u8]) -> Result<(), ScreenError<ERR>> {
+        match *plan {
+            Plan::Unchanged => Ok(()),
+            Plan::Full => self.show_full_screen_source(image),
+            Plan::Window(ref window) => self.update_windows(image, core::slice::from_ref(window)),
+            Plan::Windows(windows) => self.update_windows(image, windows),
+        }
+    }
+
+    /// Writes `windows` of `image`, a full screen image, to RAM and shows
+    /// them all with a single partial update. They are then written again
+    /// as with `show_partial_image`.
+    ///
+    /// Windows are written whole bytes at a time, so should not overlap
+    /// pixels of `image` that are not to be shown. See `coalesce`.
+    pub fn update_windows(
+        &mut self,
+        image: &[u8],
+        windows: &[Window],
+    ) -> Result<(), ScreenError<ERR>> {
+        let width = self.size.x_size();
+        let height = self.size.y_size();
+        if image.len() != image_len(width, height) {
+            return Err(ScreenError::LengthError);
+        }
+        if windows.is_empty() {
+            return Ok(());
+        }
+        for window in windows {
+            if window.w == 0 || window.h == 0
+                || window.x as u32 + window.w as u32 > width as u32
+                || window.y as u32 + window.h as u32 > height as u32
+            {
+                return Err(ScreenError::BoundsError);
+            }
+        }
+
+        for window in windows {
+            self.load_window(image, window)?;
+        }
+
+        self.load_partial_update_lut()?;
+        self.power_on()?;
+        self.update_partial()?;
+        self.power_off()?;
+
+        for window in windows {
+            self.load_window(image, window)?;
+        }
+
+        Ok(())
+    }
+
+    /// Shows what is in RAM after writing `changed_pixels` pixels, with a
+    /// partial update unless `tracker` promotes it to a full refresh. Returns
+    /// the kind of update done.
+    pub fn refresh(&mut self, tracker: &mut RefreshTracker, changed_pixels: u32) -> Result<Refresh, ERR> {
+        let refresh = tracker.next(changed_pixels);
+
+        match refresh {
+            Refresh::Full => {
+                self.load_full_update_lut()?;
+                self.power_on()?;
+                self.update_full()?;
+            }
+            Refresh::Partial => {
+                self.load_partial_update_lut()?;
+                self.power_on()?;
+                self.update_partial()?;
+            }
+        }
+        self.power_off()?;
+
+        tracker.record(refresh, changed_pixels);
+        Ok(refresh)
+    }
+
     pub fn load_full_update_lut(&mut self) -> Result<(), ERR> {
         let lut_full_update = self.lut_full;
 
-        self.write_cmd_string(Command::WriteLutRegister, &lut_full_update)
+        self.write_params(&params::Lut(lut_full_update))
     }
 
     pub fn load_partial_update_lut(&mut self) -> Result<(), ERR> {
         let lut_partial_update = self.lut_part;
 
-        self.write_cmd_string(Command::WriteLutRegister, &lut_partial_update)
+        self.write_params(&params::Lut(lut_partial_update))
     }
 
     pub fn power_on(&mut self) -> Result<(), ERR> {
-        self.write_cmd_string(Command::DisplayUpdateControl2, &[0xc0])?;
+        self.write_params(&UpdateSequence::POWER_ON)?;
         self.write_cmd(Command::MasterActivation)?;
 
         while self.busy.is_high() {}
@@ -678,7 +1357,7 @@ where
     }
 
     pub fn power_off(&mut self) -> Result<(), ERR> {
-        self.write_cmd_string(Command::DisplayUpdateControl2, &[0xc3])?;
+        self.write_params(&UpdateSequence::POWER_OFF)?;
         self.write_cmd(Command::MasterActivation)?;
 
         while self.busy.is_high() {}
@@ -697,7 +1376,7 @@ where
         // |||-------- LOAD TEMPERATURE (0x20)
         // ||--------- CP ENABLE        (0x40)
         // |---------- CLK/OSC ENABLE   (0x80)
-        self.write_cmd_string(Command::DisplayUpdateControl2, &[0xc7])?;
+        self.write_params(&UpdateSequence::FULL)?;
         self.write_cmd(Command::MasterActivation)?;
 
         while self.busy.is_high() {}
@@ -716,7 +1395,7 @@ where
         // |||-------- LOAD TEMPERATURE (0x20)
         // ||--------- CP ENABLE        (0x40)
         // |---------- CLK/OSC ENABLE   (0x80)
-        self.write_cmd_string(Command::DisplayUpdateControl2, &[0x04])?;
+        self.write_params(&UpdateSequence::PARTIAL)?;
         self.write_cmd(Command::MasterActivation)?;
 
         while self.busy.is_high() {}
@@ -724,7 +1403,7 @@ where
         self.write_cmd(Command::Nop)
     }
 
-    pub fn load_image(&mut self, image: &[u8]) -> Result<(), ERR> {
+    pub fn load_image<I: ImageSource>(&mut self, mut image: I) -> Result<(), ERR> {
         while self.busy.is_high() {}
 
         self.cs.set_low();
@@ -734,10 +1413,12 @@ where
         let _ = block!(self.serial.read())?;
 
         self.dc.set_high();
-        for d in image {
-            block!(self.serial.send(*d))?;
-            let _ = block!(self.serial.read())?;
-        }
+        let serial = &mut self.serial;
+        image.stream(|d| {
+            block!(serial.send(d))?;
+            let _ = block!(serial.read())?;
+            Ok(())
+        })?;
 
         self.cs.set_high();
 
@@ -746,31 +1427,25 @@ where
 
     /// `x_start` and `x_end` are in bytes. `y_start` and `y_end` are in pixels.
     pub fn set_display_area(&mut self, x_start: u8, x_end: u8, y_start: u16, y_end: u16) -> Result<(), ScreenError<ERR>> {
-        let x_size = width_pixels_to_bytes(self.x_size);
-        if x_start > x_size || x_end > x_size || y_start > self.y_size || y_end > self.y_size {
+        let x_size = width_pixels_to_bytes(self.size.x_size());
+        let y_size = self.size.y_size();
+        if x_start >= x_size || x_end >= x_size || y_start >= y_size || y_end >= y_size {
             return Err(ScreenError::BoundsError);
         }
 
+        let (y_start, y_end) = match self.gate_scan.direction {
+            ScanDirection::Forward => (y_start, y_end),
+            ScanDirection::Reverse => (y_size - 1 - y_start, y_size - 1 - y_end),
+        };
+
         // set x region
-        self.write_cmd_string(
-            Command::SetRamXAddressStartEndPosition,
-            &[x_start, x_end]
-        )?;
+        self.write_params(&params::RamXWindow { start: x_start, end: x_end })?;
         // set y region
-        self.write_cmd_string(
-            Command::SetRamYAddressStartEndPosition,
-            &[y_start as u8, (y_start >> 8) as u8, y_end as u8, (y_end >> 8) as u8]
-        )?;
+        self.write_params(&params::RamYWindow { start: y_start, end: y_end })?;
         // set x origin
-        self.write_cmd_string(
-            Command::SetRamXAddressCounter,
-            &[x_start]
-        )?;
+        self.write_params(&params::RamXCounter { x: x_start })?;
         // set y origin
-        self.write_cmd_string(
-            Command::SetRamYAddressCounter,
-            &[y_start as u8, (y_start >> 8) as u8]
-        )?;
+        self.write_params(&params::RamYCounter { y: y_start })?;
 
         Ok(())
     }
@@ -799,11 +1474,22 @@ where
         Ok(())
     }
 
+    /// Writes the command the parameters belong to along with their data.
+    pub fn write_params<P: Parameters>(&mut self, params: &P) -> Result<(), ERR> {
+        self.write_cmd_string(P::COMMAND, params.encode().as_ref())
+    }
+
     pub fn write_cmd_string(&mut self, cmd: Command, data: &[u8]) -> Result<(), ERR> {
+        self.write_raw(cmd as u8, data)
+    }
+
+    /// Writes any opcode, including ones not listed in `Command`, followed by
+    /// its data. Nothing is checked; the opcode and data are sent as is.
+    pub fn write_raw(&mut self, opcode: u8, data: &[u8]) -> Result<(), ERR> {
         self.cs.set_low();
 
         self.dc.set_low();
-        block!(self.serial.send(cmd as u8))?;
+        block!(self.serial.send(opcode))?;
         let _ = block!(self.serial.read())?;
 
         self.dc.set_high();
@@ -816,4 +1502,27 @@ where
 
         Ok(())
     }
+
+    /// Writes any opcode, then fills `buf` with the bytes clocked in while
+    /// sending a dummy byte of 0x00 for each, with DC high.
+    ///
+    /// Only useful when the controller is wired to drive MISO, e.g. for the
+    /// status (0x2f) or temperature (0x1b) reads.
+    pub fn read_raw(&mut self, opcode: u8, buf: &mut [u8]) -> Result<(), ERR> {
+        self.cs.set_low();
+
+        self.dc.set_low();
+        block!(self.serial.send(opcode))?;
+        let _ = block!(self.serial.read())?;
+
+        self.dc.set_high();
+        for b in buf.iter_mut() {
+            block!(self.serial.send(0x00))?;
+            *b = block!(self.serial.read())?;
+        }
+
+        self.cs.set_high();
+
+        Ok(())
+    }
 }
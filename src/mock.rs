@@ -0,0 +1,294 @@
+//! Recording implementations of the HAL traits used by `Screen`, for testing
+//! without hardware. Enabled with the `mock` feature.
+//!
+//! All of the mocks created from a `Recorder` share a single log of `Event`s.
+//!
+//! ```ignore
+//! let (recorder, screen) = Recorder::screen(ScreenBuilder::preset(Preset::CFAP200200A0_154))?;
+//! assert_eq!(recorder.commands()[0], (0x01, vec![0xc7, 0x00, 0x00]));
+//! ```
+
+use core::cell::RefCell;
+use core::convert::Infallible;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use std::vec::Vec;
+
+use embedded_hal::blocking::delay::DelayMs;
+#[allow(deprecated)]
+use embedded_hal::digital::{InputPin, OutputPin};
+use embedded_hal::spi::FullDuplex;
+
+use super::{Screen, ScreenBuilder, ScreenError};
+
+/// A `Screen` built entirely from mocks.
+pub type MockScreen<D = super::DynamicSize> =
+    Screen<MockSpi, MockPin, MockPin, MockBusy, MockPin, Infallible, D>;
+
+/// The output pins of the screen.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pin {
+    Dc,
+    Cs,
+    Reset,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Event {
+    /// The pin was driven low from high or from its initial, unknown, state.
+    Low(Pin),
+    /// The pin was driven high from low or from its initial, unknown, state.
+    High(Pin),
+    /// A byte was sent while DC was low.
+    Command(u8),
+    /// A byte was sent while DC was high.
+    Data(u8),
+    /// A blocking delay, in milliseconds.
+    Delay(u16),
+}
+
+#[derive(Debug, Default)]
+struct State {
+    events: Vec<Event>,
+    dc: Option<bool>,
+    cs: Option<bool>,
+    reset: Option<bool>,
+    /// Number of polls of BUSY left that read high.
+    busy_polls: usize,
+    /// Bytes returned by `FullDuplex::read`, 0x00 once empty.
+    miso: VecDeque<u8>,
+    /// Number of words sent without a matching read.
+    pending_reads: usize,
+}
+
+impl State {
+    fn level(&mut self, pin: Pin) -> &mut Option<bool> {
+        match pin {
+            Pin::Dc => &mut self.dc,
+            Pin::Cs => &mut self.cs,
+            Pin::Reset => &mut self.reset,
+        }
+    }
+
+    fn set(&mut self, pin: Pin, high: bool) {
+        let level = self.level(pin);
+        if *level != Some(high) {
+            *level = Some(high);
+            self.events.push(if high { Event::High(pin) } else { Event::Low(pin) });
+        }
+    }
+}
+
+/// The shared event log and the source of all of the mocks.
+#[derive(Clone, Debug, Default)]
+pub struct Recorder {
+    state: Rc<RefCell<State>>,
+}
+
+impl Recorder {
+    pub fn new() -> Recorder {
+        Recorder::default()
+    }
+
+    /// Creates a recorder and a screen built from its mocks.
+    pub fn screen(
+        builder: ScreenBuilder,
+    ) -> Result<(Recorder, MockScreen), ScreenError<Infallible>> {
+        let recorder = Recorder::new();
+        let screen = builder.new_screen(
+            recorder.spi(),
+            recorder.dc(),
+            recorder.cs(),
+            recorder.busy(),
+            recorder.reset(),
+            &mut recorder.delay(),
+        )?;
+
+        Ok((recorder, screen))
+    }
+
+    pub fn spi(&self) -> MockSpi {
+        MockSpi { state: self.state.clone() }
+    }
+
+    pub fn dc(&self) -> MockPin {
+        MockPin { state: self.state.clone(), pin: Pin::Dc }
+    }
+
+    pub fn cs(&self) -> MockPin {
+        MockPin { state: self.state.clone(), pin: Pin::Cs }
+    }
+
+    pub fn reset(&self) -> MockPin {
+        MockPin { state: self.state.clone(), pin: Pin::Reset }
+    }
+
+    pub fn busy(&self) -> MockBusy {
+        MockBusy { state: self.state.clone() }
+    }
+
+    pub fn delay(&self) -> MockDelay {
+        MockDelay { state: self.state.clone() }
+    }
+
+    /// Makes the next `polls` reads of BUSY return high.
+    pub fn set_busy_polls(&self, polls: usize) {
+        self.state.borrow_mut().busy_polls = polls;
+    }
+
+    /// Number of polls of BUSY left that will read high.
+    pub fn busy_polls(&self) -> usize {
+        self.state.borrow().busy_polls
+    }
+
+    /// Queues bytes to be returned by `FullDuplex::read`.
+    pub fn queue_miso(&self, bytes: &[u8]) {
+        self.state.borrow_mut().miso.extend(bytes.iter().cloned());
+    }
+
+    /// All events recorded so far.
+    pub fn events(&self) -> Vec<Event> {
+        self.state.borrow().events.clone()
+    }
+
+    /// Removes and returns all events recorded so far.
+    pub fn take(&self) -> Vec<Event> {
+        core::mem::take(&mut self.state.borrow_mut().events)
+    }
+
+    /// Discards all events recorded so far.
+    pub fn clear(&self) {
+        self.state.borrow_mut().events.clear();
+    }
+
+    /// The bytes sent so far grouped as the controller sees them: each command
+    /// byte followed by all the data bytes sent before the next command,
+    /// whether or not they were in the same CS frame.
+    pub fn commands(&self) -> Vec<(u8, Vec<u8>)> {
+        let mut commands: Vec<(u8, Vec<u8>)> = Vec::new();
+
+        for event in self.state.borrow().events.iter() {
+            match *event {
+                Event::Command(cmd) => commands.push((cmd, Vec::new())),
+                Event::Data(data) => match commands.last_mut() {
+                    Some(cmd) => cmd.1.push(data),
+                    None => panic!("data byte 0x{:02x} sent before any command", data),
+                },
+                _ => {}
+            }
+        }
+
+        commands
+    }
+
+    /// All bytes sent so far as (DC, byte) pairs, in the form taken by
+    /// `decode::decode`.
+    pub fn bytes(&self) -> Vec<(bool, u8)> {
+        self.state
+            .borrow()
+            .events
+            .iter()
+            .filter_map(|event| match *event {
+                Event::Command(byte) => Some((false, byte)),
+                Event::Data(byte) => Some((true, byte)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The bytes sent so far in each CS frame, as (DC, byte) pairs.
+    pub fn frames(&self) -> Vec<Vec<(bool, u8)>> {
+        let mut frames = Vec::new();
+        let mut frame = None;
+        let mut dc = false;
+
+        for event in self.state.borrow().events.iter() {
+            match *event {
+                Event::Low(Pin::Cs) => frame = Some(Vec::new()),
+                Event::High(Pin::Cs) => frames.extend(frame.take()),
+                Event::Low(Pin::Dc) => dc = false,
+                Event::High(Pin::Dc) => dc = true,
+                Event::Command(byte) | Event::Data(byte) => match frame {
+                    Some(ref mut frame) => frame.push((dc, byte)),
+                    None => panic!("byte 0x{:02x} sent while CS was high", byte),
+                },
+                _ => {}
+            }
+        }
+
+        frames
+    }
+}
+
+pub struct MockSpi {
+    state: Rc<RefCell<State>>,
+}
+
+impl FullDuplex<u8> for MockSpi {
+    type Error = Infallible;
+
+    fn read(&mut self) -> nb::Result<u8, Infallible> {
+        let mut state = self.state.borrow_mut();
+        assert!(state.pending_reads > 0, "read without a preceding send");
+        state.pending_reads -= 1;
+        Ok(state.miso.pop_front().unwrap_or(0x00))
+    }
+
+    fn send(&mut self, byte: u8) -> nb::Result<(), Infallible> {
+        let mut state = self.state.borrow_mut();
+        state.pending_reads += 1;
+        let event = match state.dc {
+            Some(true) => Event::Data(byte),
+            _ => Event::Command(byte),
+        };
+        state.events.push(event);
+        Ok(())
+    }
+}
+
+pub struct MockPin {
+    state: Rc<RefCell<State>>,
+    pin: Pin,
+}
+
+#[allow(deprecated)]
+impl OutputPin for MockPin {
+    fn set_low(&mut self) {
+        self.state.borrow_mut().set(self.pin, false);
+    }
+
+    fn set_high(&mut self) {
+        self.state.borrow_mut().set(self.pin, true);
+    }
+}
+
+pub struct MockBusy {
+    state: Rc<RefCell<State>>,
+}
+
+#[allow(deprecated)]
+impl InputPin for MockBusy {
+    fn is_high(&self) -> bool {
+        let mut state = self.state.borrow_mut();
+        if state.busy_polls > 0 {
+            state.busy_polls -= 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn is_low(&self) -> bool {
+        !self.is_high()
+    }
+}
+
+pub struct MockDelay {
+    state: Rc<RefCell<State>>,
+}
+
+impl DelayMs<u16> for MockDelay {
+    fn delay_ms(&mut self, ms: u16) {
+        self.state.borrow_mut().events.push(Event::Delay(ms));
+    }
+}
@@ -0,0 +1,556 @@
+//! Typed command parameters.
+//!
+//! Each type here is the data of one `Command`, encoding to and decoding from
+//! the exact bytes described in the documentation of that command. Values
+//! wider than their field are truncated when encoded; decoding rejects data
+//! with reserved bits set.
+//!
+//! ```ignore
+//! screen.write_params(&RamYWindow { start: 199, end: 0 })?;
+//! ```
+
+use core::ops::{BitOr, BitOrAssign};
+
+use super::{Command, EntryMode, GateScan};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParamError {
+    /// The data is not the length taken by the command.
+    LengthError,
+    /// A reserved bit is set, or a field holds a reserved value.
+    ReservedError,
+}
+
+/// The data of a command.
+pub trait Parameters: Sized {
+    /// The command the parameters belong to.
+    const COMMAND: Command;
+
+    /// The encoded data, a `[u8; N]`.
+    type Bytes: AsRef<[u8]>;
+
+    fn encode(&self) -> Self::Bytes;
+
+    fn decode(data: &[u8]) -> Result<Self, ParamError>;
+}
+
+fn exact<const N: usize>(data: &[u8]) -> Result<[u8; N], ParamError> {
+    let mut bytes = [0; N];
+    if data.len() != N {
+        return Err(ParamError::LengthError);
+    }
+    bytes.copy_from_slice(data);
+    Ok(bytes)
+}
+
+fn check_reserved(byte: u8, mask: u8) -> Result<(), ParamError> {
+    if byte & !mask != 0 {
+        Err(ParamError::ReservedError)
+    } else {
+        Ok(())
+    }
+}
+
+fn encode_u9(value: u16) -> [u8; 2] {
+    [value as u8, ((value >> 8) & 0x01) as u8]
+}
+
+fn decode_u9(low: u8, high: u8) -> Result<u16, ParamError> {
+    check_reserved(high, 0x01)?;
+    Ok(low as u16 | (high as u16) << 8)
+}
+
+/// `Command::DriverOutputControl`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DriverOutput {
+    /// Number of gates, from 1 to 512.
+    pub gates: u16,
+    pub gate_scan: GateScan,
+}
+
+impl Parameters for DriverOutput {
+    const COMMAND: Command = Command::DriverOutputControl;
+    type Bytes = [u8; 3];
+
+    fn encode(&self) -> [u8; 3] {
+        // MUX is the number of gates minus one
+        let mux = encode_u9(self.gates.wrapping_sub(1));
+        [mux[0], mux[1], self.gate_scan.bits()]
+    }
+
+    fn decode(data: &[u8]) -> Result<DriverOutput, ParamError> {
+        let data: [u8; 3] = exact(data)?;
+        Ok(DriverOutput {
+            gates: decode_u9(data[0], data[1])? + 1,
+            gate_scan: GateScan::from_bits(data[2]).ok_or(ParamError::ReservedError)?,
+        })
+    }
+}
+
+/// `Command::BoosterSoftStartControl`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SoftStart {
+    pub phase1: u8,
+    pub phase2: u8,
+    pub phase3: u8,
+}
+
+impl Parameters for SoftStart {
+    const COMMAND: Command = Command::BoosterSoftStartControl;
+    type Bytes = [u8; 3];
+
+    fn encode(&self) -> [u8; 3] {
+        [self.phase1, self.phase2, self.phase3]
+    }
+
+    fn decode(data: &[u8]) -> Result<SoftStart, ParamError> {
+        let data: [u8; 3] = exact(data)?;
+        Ok(SoftStart {
+            phase1: data[0],
+            phase2: data[1],
+            phase3: data[2],
+        })
+    }
+}
+
+/// `Command::GateScanStartPosition`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GateScanStart {
+    pub position: u16,
+}
+
+impl Parameters for GateScanStart {
+    const COMMAND: Command = Command::GateScanStartPosition;
+    type Bytes = [u8; 2];
+
+    fn encode(&self) -> [u8; 2] {
+        encode_u9(self.position)
+    }
+
+    fn decode(data: &[u8]) -> Result<GateScanStart, ParamError> {
+        let data: [u8; 2] = exact(data)?;
+        Ok(GateScanStart {
+            position: decode_u9(data[0], data[1])?,
+        })
+    }
+}
+
+/// `Command::DeepSleepMode`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DeepSleep {
+    pub enabled: bool,
+}
+
+impl Parameters for DeepSleep {
+    const COMMAND: Command = Command::DeepSleepMode;
+    type Bytes = [u8; 1];
+
+    fn encode(&self) -> [u8; 1] {
+        [self.enabled as u8]
+    }
+
+    fn decode(data: &[u8]) -> Result<DeepSleep, ParamError> {
+        let data: [u8; 1] = exact(data)?;
+        check_reserved(data[0], 0x01)?;
+        Ok(DeepSleep {
+            enabled: data[0] != 0,
+        })
+    }
+}
+
+/// AM, the direction the address counter is updated in after a RAM write.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AddressDirection {
+    X = 0,
+    Y = 1,
+}
+
+/// `Command::DataEntryModeSetting`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DataEntryMode {
+    pub id: EntryMode,
+    pub am: AddressDirection,
+}
+
+impl Parameters for DataEntryMode {
+    const COMMAND: Command = Command::DataEntryModeSetting;
+    type Bytes = [u8; 1];
+
+    fn encode(&self) -> [u8; 1] {
+        [(self.am as u8) << 2 | self.id as u8]
+    }
+
+    fn decode(data: &[u8]) -> Result<DataEntryMode, ParamError> {
+        let data: [u8; 1] = exact(data)?;
+        check_reserved(data[0], 0b111)?;
+        Ok(DataEntryMode {
+            id: EntryMode::from_bits(data[0] & 0b11).ok_or(ParamError::ReservedError)?,
+            am: if data[0] & 0b100 != 0 { AddressDirection::Y } else { AddressDirection::X },
+        })
+    }
+}
+
+/// `Command::TemperatureSensorControl`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Temperature {
+    /// Temperature in sixteenths of a degree Celsius, from -2048 to 2047.
+    pub sixteenths: i16,
+}
+
+impl Temperature {
+    pub fn from_celsius(celsius: i8) -> Temperature {
+        Temperature {
+            sixteenths: celsius as i16 * 16,
+        }
+    }
+}
+
+impl Parameters for Temperature {
+    const COMMAND: Command = Command::TemperatureSensorControl;
+    type Bytes = [u8; 2];
+
+    fn encode(&self) -> [u8; 2] {
+        let raw = (self.sixteenths as u16) << 4;
+        [(raw >> 8) as u8, raw as u8]
+    }
+
+    fn decode(data: &[u8]) -> Result<Temperature, ParamError> {
+        let data: [u8; 2] = exact(data)?;
+        check_reserved(data[1], 0xf0)?;
+        Ok(Temperature {
+            // sign extend A[11:0]
+            sixteenths: ((data[0] as u16) << 8 | data[1] as u16) as i16 >> 4,
+        })
+    }
+}
+
+/// `Command::DisplayUpdateControl1`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UpdateOptions {
+    /// Bypass old RAM.
+    pub bypass: bool,
+    /// Value used as new RAM for bypass.
+    pub bypass_value: bool,
+    /// C[1:0], whose meaning is unknown.
+    pub c: u8,
+    /// GS[1:0]
+    pub gs: u8,
+}
+
+impl Parameters for UpdateOptions {
+    const COMMAND: Command = Command::DisplayUpdateControl1;
+    type Bytes = [u8; 1];
+
+    fn encode(&self) -> [u8; 1] {
+        [(self.bypass as u8) << 7
+            | (self.bypass_value as u8) << 4
+            | (self.c & 0b11) << 2
+            | self.gs & 0b11]
+    }
+
+    fn decode(data: &[u8]) -> Result<UpdateOptions, ParamError> {
+        let data: [u8; 1] = exact(data)?;
+        check_reserved(data[0], 0b1001_1111)?;
+        Ok(UpdateOptions {
+            bypass: data[0] & 0x80 != 0,
+            bypass_value: data[0] & 0x10 != 0,
+            c: (data[0] >> 2) & 0b11,
+            gs: data[0] & 0b11,
+        })
+    }
+}
+
+/// `Command::DisplayUpdateControl2`, the stages of the Display Update
+/// Sequence. Stages can be combined with `|`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UpdateSequence(u8);
+
+impl UpdateSequence {
+    pub const CLOCK_ENABLE: UpdateSequence = UpdateSequence(0x80);
+    pub const ANALOG_ENABLE: UpdateSequence = UpdateSequence(0x40);
+    pub const LOAD_TEMPERATURE: UpdateSequence = UpdateSequence(0x20);
+    pub const LOAD_LUT: UpdateSequence = UpdateSequence(0x10);
+    pub const INITIAL_DISPLAY: UpdateSequence = UpdateSequence(0x08);
+    pub const DISPLAY_PATTERN: UpdateSequence = UpdateSequence(0x04);
+    pub const ANALOG_DISABLE: UpdateSequence = UpdateSequence(0x02);
+    pub const CLOCK_DISABLE: UpdateSequence = UpdateSequence(0x01);
+
+    /// No stages.
+    pub const EMPTY: UpdateSequence = UpdateSequence(0x00);
+    /// Enables the clock and analog circuits, used by `Screen::power_on`.
+    pub const POWER_ON: UpdateSequence = UpdateSequence(0xc0);
+    /// Enables then disables the clock and analog circuits, used by
+    /// `Screen::power_off`.
+    pub const POWER_OFF: UpdateSequence = UpdateSequence(0xc3);
+    /// Powers on, displays the pattern, and powers off, used by
+    /// `Screen::update_full`.
+    pub const FULL: UpdateSequence = UpdateSequence(0xc7);
+    /// Displays the pattern only, used by `Screen::update_partial`.
+    pub const PARTIAL: UpdateSequence = UpdateSequence(0x04);
+
+    pub const fn from_bits(bits: u8) -> UpdateSequence {
+        UpdateSequence(bits)
+    }
+
+    pub const fn bits(&self) -> u8 {
+        self.0
+    }
+
+    pub const fn contains(&self, other: UpdateSequence) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for UpdateSequence {
+    type Output = UpdateSequence;
+
+    fn bitor(self, rhs: UpdateSequence) -> UpdateSequence {
+        UpdateSequence(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for UpdateSequence {
+    fn bitor_assign(&mut self, rhs: UpdateSequence) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl Parameters for UpdateSequence {
+    const COMMAND: Command = Command::DisplayUpdateControl2;
+    type Bytes = [u8; 1];
+
+    fn encode(&self) -> [u8; 1] {
+        [self.0]
+    }
+
+    fn decode(data: &[u8]) -> Result<UpdateSequence, ParamError> {
+        let data: [u8; 1] = exact(data)?;
+        Ok(UpdateSequence(data[0]))
+    }
+}
+
+/// `Command::WriteVcomRegister`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Vcom {
+    pub value: u8,
+}
+
+impl Parameters for Vcom {
+    const COMMAND: Command = Command::WriteVcomRegister;
+    type Bytes = [u8; 1];
+
+    fn encode(&self) -> [u8; 1] {
+        [self.value]
+    }
+
+    fn decode(data: &[u8]) -> Result<Vcom, ParamError> {
+        let data: [u8; 1] = exact(data)?;
+        Ok(Vcom { value: data[0] })
+    }
+}
+
+/// `Command::WriteLutRegister`. See `Waveform` for a structured view of the
+/// LUT.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Lut(pub [u8; 30]);
+
+impl Parameters for Lut {
+    const COMMAND: Command = Command::WriteLutRegister;
+    type Bytes = [u8; 30];
+
+    fn encode(&self) -> [u8; 30] {
+        self.0
+    }
+
+    fn decode(data: &[u8]) -> Result<Lut, ParamError> {
+        Ok(Lut(exact(data)?))
+    }
+}
+
+/// `Command::SetDummyLinePeriod`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DummyLinePeriod {
+    /// 7 bits
+    pub period: u8,
+}
+
+impl Parameters for DummyLinePeriod {
+    const COMMAND: Command = Command::SetDummyLinePeriod;
+    type Bytes = [u8; 1];
+
+    fn encode(&self) -> [u8; 1] {
+        [self.period & 0x7f]
+    }
+
+    fn decode(data: &[u8]) -> Result<DummyLinePeriod, ParamError> {
+        let data: [u8; 1] = exact(data)?;
+        check_reserved(data[0], 0x7f)?;
+        Ok(DummyLinePeriod { period: data[0] })
+    }
+}
+
+/// `Command::SetGateLineWidth`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GateLineWidth {
+    /// 4 bits
+    pub width: u8,
+}
+
+impl Parameters for GateLineWidth {
+    const COMMAND: Command = Command::SetGateLineWidth;
+    type Bytes = [u8; 1];
+
+    fn encode(&self) -> [u8; 1] {
+        [self.width & 0x0f]
+    }
+
+    fn decode(data: &[u8]) -> Result<GateLineWidth, ParamError> {
+        let data: [u8; 1] = exact(data)?;
+        check_reserved(data[0], 0x0f)?;
+        Ok(GateLineWidth { width: data[0] })
+    }
+}
+
+/// Fix level setting for VBD.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BorderLevel {
+    Vss = 0b00,
+    Vsh = 0b01,
+    Vsl = 0b10,
+    HiZ = 0b11,
+}
+
+/// `Command::BorderWaveformControl`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BorderWaveform {
+    /// Follow source at initial Update Display.
+    pub follow_source: bool,
+    /// Use `level` rather than the GS transition for VBD.
+    pub fix_level: bool,
+    pub level: BorderLevel,
+    /// GS transition for VBD.
+    pub gs: u8,
+}
+
+impl Parameters for BorderWaveform {
+    const COMMAND: Command = Command::BorderWaveformControl;
+    type Bytes = [u8; 1];
+
+    fn encode(&self) -> [u8; 1] {
+        [(self.follow_source as u8) << 7
+            | (self.fix_level as u8) << 6
+            | (self.level as u8) << 4
+            | self.gs & 0b11]
+    }
+
+    fn decode(data: &[u8]) -> Result<BorderWaveform, ParamError> {
+        let data: [u8; 1] = exact(data)?;
+        check_reserved(data[0], 0b1111_0011)?;
+        Ok(BorderWaveform {
+            follow_source: data[0] & 0x80 != 0,
+            fix_level: data[0] & 0x40 != 0,
+            level: match (data[0] >> 4) & 0b11 {
+                0b00 => BorderLevel::Vss,
+                0b01 => BorderLevel::Vsh,
+                0b10 => BorderLevel::Vsl,
+                _ => BorderLevel::HiZ,
+            },
+            gs: data[0] & 0b11,
+        })
+    }
+}
+
+/// `Command::SetRamXAddressStartEndPosition`, in bytes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RamXWindow {
+    pub start: u8,
+    pub end: u8,
+}
+
+impl Parameters for RamXWindow {
+    const COMMAND: Command = Command::SetRamXAddressStartEndPosition;
+    type Bytes = [u8; 2];
+
+    fn encode(&self) -> [u8; 2] {
+        [self.start & 0x1f, self.end & 0x1f]
+    }
+
+    fn decode(data: &[u8]) -> Result<RamXWindow, ParamError> {
+        let data: [u8; 2] = exact(data)?;
+        check_reserved(data[0], 0x1f)?;
+        check_reserved(data[1], 0x1f)?;
+        Ok(RamXWindow {
+            start: data[0],
+            end: data[1],
+        })
+    }
+}
+
+/// `Command::SetRamYAddressStartEndPosition`, in pixels.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RamYWindow {
+    pub start: u16,
+    pub end: u16,
+}
+
+impl Parameters for RamYWindow {
+    const COMMAND: Command = Command::SetRamYAddressStartEndPosition;
+    type Bytes = [u8; 4];
+
+    fn encode(&self) -> [u8; 4] {
+        let start = encode_u9(self.start);
+        let end = encode_u9(self.end);
+        [start[0], start[1], end[0], end[1]]
+    }
+
+    fn decode(data: &[u8]) -> Result<RamYWindow, ParamError> {
+        let data: [u8; 4] = exact(data)?;
+        Ok(RamYWindow {
+            start: decode_u9(data[0], data[1])?,
+            end: decode_u9(data[2], data[3])?,
+        })
+    }
+}
+
+/// `Command::SetRamXAddressCounter`, in bytes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RamXCounter {
+    pub x: u8,
+}
+
+impl Parameters for RamXCounter {
+    const COMMAND: Command = Command::SetRamXAddressCounter;
+    type Bytes = [u8; 1];
+
+    fn encode(&self) -> [u8; 1] {
+        [self.x & 0x1f]
+    }
+
+    fn decode(data: &[u8]) -> Result<RamXCounter, ParamError> {
+        let data: [u8; 1] = exact(data)?;
+        check_reserved(data[0], 0x1f)?;
+        Ok(RamXCounter { x: data[0] })
+    }
+}
+
+/// `Command::SetRamYAddressCounter`, in pixels.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RamYCounter {
+    pub y: u16,
+}
+
+impl Parameters for RamYCounter {
+    const COMMAND: Command = Command::SetRamYAddressCounter;
+    type Bytes = [u8; 2];
+
+    fn encode(&self) -> [u8; 2] {
+        encode_u9(self.y)
+    }
+
+    fn decode(data: &[u8]) -> Result<RamYCounter, ParamError> {
+        let data: [u8; 2] = exact(data)?;
+        Ok(RamYCounter {
+            y: decode_u9(data[0], data[1])?,
+        })
+    }
+}
@@ -0,0 +1,290 @@
+//! Choosing how to show a new frame: not at all, with partial updates of
+//! the windows that changed, or with a full refresh.
+//!
+//! `PlanPolicy::plan` compares the old and new frames and weighs the cost of
+//! each, and `Screen::show_plan` carries out the result. `coalesce` merges
+//! dirty rectangles tracked by the caller into windows for
+//! `Screen::update_windows`.
+
+use super::{image_len, width_pixels_to_bytes};
+
+/// A rectangle of the screen in pixels, from the top left. Windows from a
+/// `Plan` start and end on a byte, or at the right edge of the screen.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Window {
+    pub x: u16,
+    pub y: u16,
+    pub w: u16,
+    pub h: u16,
+}
+
+impl Window {
+    /// Bytes of image data in the window.
+    pub fn bytes(&self) -> u32 {
+        let x_start = self.x as u32 / 8;
+        let x_end = (self.x_end() + 7) / 8;
+        (x_end - x_start) * self.h as u32
+    }
+
+    /// True if the windows share a pixel.
+    pub fn overlaps(&self, other: &Window) -> bool {
+        (self.x as u32) < other.x_end() && (other.x as u32) < self.x_end()
+            && (self.y as u32) < other.y_end() && (other.y as u32) < self.y_end()
+    }
+
+    /// The smallest window covering both, with its width and height limited
+    /// to `u16::MAX`.
+    pub fn union(&self, other: &Window) -> Window {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        let x_end = self.x_end().max(other.x_end());
+        let y_end = self.y_end().max(other.y_end());
+        Window {
+            x,
+            y,
+            w: (x_end - x as u32).min(u16::MAX as u32) as u16,
+            h: (y_end - y as u32).min(u16::MAX as u32) as u16,
+        }
+    }
+
+    /// One past the rightmost pixel, which may be past `u16::MAX`.
+    fn x_end(&self) -> u32 {
+        self.x as u32 + self.w as u32
+    }
+
+    /// One past the bottom row, which may be past `u16::MAX`.
+    fn y_end(&self) -> u32 {
+        self.y as u32 + self.h as u32
+    }
+}
+
+/// How to show a new frame, from `PlanPolicy::plan`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Plan<'a> {
+    /// Nothing changed.
+    Unchanged,
+    /// A partial update of one window.
+    Window(Window),
+    /// A partial update of several windows.
+    Windows(&'a [Window]),
+    /// A full refresh.
+    Full,
+}
+
+/// Weighs the time of updates against ghosting when choosing how to show a
+/// new frame. Times are estimates for the panel and SPI clock in use.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PlanPolicy {
+    /// Time of a full refresh.
+    pub full_update_us: u32,
+    /// Time of a partial update, not counting writing its windows.
+    pub partial_update_us: u32,
+    /// Cost of each window, for setting the RAM area to write it.
+    pub window_us: u32,
+    /// Cost of each byte of a window, for writing it and for the ghosting
+    /// left by refreshing pixels in it that did not change.
+    pub byte_us: u32,
+    /// Most windows in a plan. Changed areas beyond this are merged.
+    pub max_windows: usize,
+    /// Percentage of the screen changed above which a full refresh is used,
+    /// as a partial update of that much leaves visible ghosting.
+    pub max_changed_percent: u8,
+}
+
+/// Timings of the CFAP200200A0-154, at most 4 windows, and a full refresh
+/// when more than half of the screen changes.
+impl Default for PlanPolicy {
+    fn default() -> PlanPolicy {
+        PlanPolicy {
+            full_update_us: 2_000_000,
+            partial_update_us: 300_000,
+            window_us: 1_000,
+            byte_us: 100,
+            max_windows: 4,
+            max_changed_percent: 50,
+        }
+    }
+}
+
+impl PlanPolicy {
+    /// Time of a partial update of `windows`.
+    pub fn partial_cost(&self, windows: &[Window]) -> u32 {
+        windows.iter().fold(self.partial_update_us, |cost, window| {
+            cost.saturating_add(self.window_us)
+                .saturating_add(window.bytes().saturating_mul(self.byte_us))
+        })
+    }
+
+    /// Compares `old`, the frame on the screen, with `new`, both full screen
+    /// images of `width` by `height` pixels, and chooses the quickest way to
+    /// show `new`. Windows are written to `windows`, which limits their
+    /// number along with `max_windows`.
+    ///
+    /// Returns `None` if either image is the wrong length.
+    pub fn plan<'w>(
+        &self,
+        width: u16,
+        height: u16,
+        old: &[u8],
+        new: &[u8],
+        windows: &'w mut [Window],
+    ) -> Option<Plan<'w>> {
+        let len = image_len(width, height);
+        if old.len() != len || new.len() != len {
+            return None;
+        }
+
+        let limit = self.max_windows.min(windows.len());
+        let row_bytes = width_pixels_to_bytes(width) as usize;
+        let mut count = 0;
+        let mut changed_pixels = 0u32;
+
+        for y in 0..height {
+            let start = y as usize * row_bytes;
+            let old_row = &old[start..start + row_bytes];
+            let new_row = &new[start..start + row_bytes];
+
+            let mut first = None;
+            let mut last = 0;
+            for (i, (a, b)) in old_row.iter().zip(new_row).enumerate() {
+                let diff = a ^ b;
+                if diff != 0 {
+                    first = first.or(Some(i));
+                    last = i;
+                    changed_pixels += diff.count_ones();
+                }
+            }
+            let first = match first {
+                Some(first) => first,
+                None => continue,
+            };
+
+            let x = first as u16 * 8;
+            let row = Window {
+                x,
+                y,
+                w: ((last as u16 + 1) * 8).min(width) - x,
+                h: 1,
+            };
+            // rows that follow on extend the last window
+            if count > 0 && windows[count - 1].y + windows[count - 1].h == y {
+                windows[count - 1] = windows[count - 1].union(&row);
+                continue;
+            }
+            if limit == 0 {
+                return Some(Plan::Full);
+            }
+            if count == limit {
+                // no room, so grow the window that grows least
+                let i = (0..count)
+                    .min_by_key(|&i| extra_bytes(&windows[i], &row))
+                    .unwrap_or(0);
+                windows[i] = windows[i].union(&row);
+                continue;
+            }
+            windows[count] = row;
+            count += 1;
+        }
+
+        if count == 0 {
+            return Some(Plan::Unchanged);
+        }
+        let screen_pixels = width as u32 * height as u32;
+        if changed_pixels as u64 * 100 > self.max_changed_percent as u64 * screen_pixels as u64 {
+            return Some(Plan::Full);
+        }
+
+        // merge while it saves more in windows than it costs in bytes
+        let count = merge_windows(&mut windows[..count], limit, |extra| {
+            extra.saturating_mul(self.byte_us) < self.window_us
+        });
+        let windows = &windows[..count];
+
+        if self.partial_cost(windows) >= self.full_update_us {
+            return Some(Plan::Full);
+        }
+
+        Some(if count == 1 {
+            Plan::Window(windows[0])
+        } else {
+            Plan::Windows(windows)
+        })
+    }
+}
+
+/// Bytes written to set the RAM area of a window, which merging two windows
+/// saves.
+const WINDOW_BYTES: u32 = 14;
+
+/// Merges dirty rectangles into at most `max_windows` windows, for
+/// `Screen::update_windows`, and returns how many there are. They are left at
+/// the start of `windows`, in order from the top.
+///
+/// Rectangles are clipped to a screen of `width` by `height` pixels, widened
+/// to start and end on a byte, and merged where they overlap or where merging
+/// writes fewer bytes than it saves in setting RAM areas. Past that the
+/// windows whose merge adds least are merged until there are at most
+/// `max_windows`, which is taken to be at least one.
+pub fn coalesce(windows: &mut [Window], width: u16, height: u16, max_windows: usize) -> usize {
+    let mut count = 0;
+    for i in 0..windows.len() {
+        let Window { x, y, w, h } = windows[i];
+        let x_end = (x as u32 + w as u32).min(width as u32) as u16;
+        let y_end = (y as u32 + h as u32).min(height as u32) as u16;
+        if x >= x_end || y >= y_end {
+            continue;
+        }
+
+        let x = x / 8 * 8;
+        let x_end = ((x_end as u32 + 7) / 8 * 8).min(width as u32) as u16;
+        windows[count] = Window { x, y, w: x_end - x, h: y_end - y };
+        count += 1;
+    }
+
+    merge_windows(&mut windows[..count], max_windows.max(1), |extra| extra <= WINDOW_BYTES)
+}
+
+/// Bytes of the union of `a` and `b` not in either.
+fn extra_bytes(a: &Window, b: &Window) -> u32 {
+    a.union(b).bytes().saturating_sub(a.bytes() + b.bytes())
+}
+
+/// Merges overlapping windows, then windows for which `worth` returns true
+/// given the extra bytes of their union, then those whose union adds least
+/// until there are at most `max`. Returns the number left at the start of
+/// `windows`, which are sorted from the top.
+fn merge_windows<F: Fn(u32) -> bool>(windows: &mut [Window], max: usize, worth: F) -> usize {
+    let mut count = windows.len();
+    loop {
+        // the overlapping pair, or failing that any pair, adding least
+        let mut best: Option<(bool, u32, usize, usize)> = None;
+        for i in 0..count {
+            for j in i + 1..count {
+                let overlaps = windows[i].overlaps(&windows[j]);
+                let extra = extra_bytes(&windows[i], &windows[j]);
+                let better = match best {
+                    None => true,
+                    Some((best_overlaps, best_extra, _, _)) => {
+                        overlaps && !best_overlaps
+                            || overlaps == best_overlaps && extra < best_extra
+                    }
+                };
+                if better {
+                    best = Some((overlaps, extra, i, j));
+                }
+            }
+        }
+
+        match best {
+            Some((overlaps, extra, i, j)) if overlaps || count > max || worth(extra) => {
+                windows[i] = windows[i].union(&windows[j]);
+                windows[j] = windows[count - 1];
+                count -= 1;
+            }
+            _ => break,
+        }
+    }
+
+    windows[..count].sort_unstable_by_key(|window| (window.y, window.x));
+    count
+}
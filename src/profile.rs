@@ -0,0 +1,133 @@
+//! Binary panel profiles for storing a `ScreenBuilder` in flash or EEPROM.
+//!
+//! A profile is `PROFILE_LEN` bytes. Multi-byte values are little endian.
+//!
+//! | Offset | Length | Field                               |
+//! |--------|--------|-------------------------------------|
+//! | 0      | 2      | Magic, `PROFILE_MAGIC`              |
+//! | 2      | 1      | Version, `PROFILE_VERSION`          |
+//! | 3      | 2      | `x_size`                            |
+//! | 5      | 2      | `y_size`                            |
+//! | 7      | 3      | `soft_start`                        |
+//! | 10     | 1      | `vcom`                              |
+//! | 11     | 1      | `dummy_line`                        |
+//! | 12     | 1      | `gate_line`                         |
+//! | 13     | 1      | `entry_mode`                        |
+//! | 14     | 1      | `gate_scan`, as `GateScan::bits`    |
+//! | 15     | 30     | `lut_full`                          |
+//! | 45     | 30     | `lut_part`                          |
+//! | 75     | 2      | CRC-16/CCITT-FALSE of bytes 0 to 74 |
+
+use super::{ConfigError, EntryMode, GateScan, ScreenBuilder};
+
+pub const PROFILE_MAGIC: [u8; 2] = *b"CF";
+pub const PROFILE_VERSION: u8 = 1;
+pub const PROFILE_LEN: usize = 77;
+
+const CRC_OFFSET: usize = PROFILE_LEN - 2;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProfileError {
+    /// The profile is shorter than `PROFILE_LEN`.
+    LengthError,
+    /// The profile does not start with `PROFILE_MAGIC`.
+    MagicError,
+    /// The profile was written by an unsupported version.
+    VersionError(u8),
+    /// The checksum does not match the contents of the profile.
+    ChecksumError,
+    /// A field holds a value that cannot be decoded.
+    FieldError,
+    /// The profile decoded but is outside of the limits of the controller.
+    ConfigError(ConfigError),
+}
+
+impl ScreenBuilder {
+    /// Serialises the builder into a profile.
+    pub fn to_profile(&self) -> [u8; PROFILE_LEN] {
+        let mut profile = [0; PROFILE_LEN];
+
+        profile[0..2].copy_from_slice(&PROFILE_MAGIC);
+        profile[2] = PROFILE_VERSION;
+        profile[3..5].copy_from_slice(&self.x_size.to_le_bytes());
+        profile[5..7].copy_from_slice(&self.y_size.to_le_bytes());
+        profile[7..10].copy_from_slice(&self.soft_start);
+        profile[10] = self.vcom;
+        profile[11] = self.dummy_line;
+        profile[12] = self.gate_line;
+        profile[13] = self.entry_mode as u8;
+        profile[14] = self.gate_scan.bits();
+        profile[15..45].copy_from_slice(&self.lut_full);
+        profile[45..75].copy_from_slice(&self.lut_part);
+
+        let crc = crc16(&profile[..CRC_OFFSET]);
+        profile[CRC_OFFSET..].copy_from_slice(&crc.to_le_bytes());
+
+        profile
+    }
+
+    /// Deserialises a builder from a profile. Only the first `PROFILE_LEN`
+    /// bytes of `profile` are read. The decoded configuration is validated.
+    pub fn from_profile(profile: &[u8]) -> Result<ScreenBuilder, ProfileError> {
+        if profile.len() < PROFILE_LEN {
+            return Err(ProfileError::LengthError);
+        }
+        let profile = &profile[..PROFILE_LEN];
+
+        if profile[0..2] != PROFILE_MAGIC {
+            return Err(ProfileError::MagicError);
+        }
+        if profile[2] != PROFILE_VERSION {
+            return Err(ProfileError::VersionError(profile[2]));
+        }
+
+        let crc = u16::from_le_bytes([profile[CRC_OFFSET], profile[CRC_OFFSET + 1]]);
+        if crc != crc16(&profile[..CRC_OFFSET]) {
+            return Err(ProfileError::ChecksumError);
+        }
+
+        let mut soft_start = [0; 3];
+        soft_start.copy_from_slice(&profile[7..10]);
+        let mut lut_full = [0; 30];
+        lut_full.copy_from_slice(&profile[15..45]);
+        let mut lut_part = [0; 30];
+        lut_part.copy_from_slice(&profile[45..75]);
+
+        let builder = ScreenBuilder {
+            x_size: u16::from_le_bytes([profile[3], profile[4]]),
+            y_size: u16::from_le_bytes([profile[5], profile[6]]),
+
+            soft_start,
+            vcom: profile[10],
+            dummy_line: profile[11],
+            gate_line: profile[12],
+            entry_mode: EntryMode::from_bits(profile[13]).ok_or(ProfileError::FieldError)?,
+            gate_scan: GateScan::from_bits(profile[14]).ok_or(ProfileError::FieldError)?,
+
+            lut_full,
+            lut_part,
+        };
+
+        builder.validate().map_err(ProfileError::ConfigError)?;
+
+        Ok(builder)
+    }
+}
+
+/// CRC-16/CCITT-FALSE: polynomial 0x1021, initial value 0xFFFF.
+fn crc16(data: &[u8]) -> u16 {
+    let mut crc = 0xffff_u16;
+
+    for byte in data {
+        crc ^= (*byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+    }
+
+    crc
+}
@@ -0,0 +1,686 @@
+//! QR code encoding, for drawing codes such as provisioning links on the
+//! panel.
+//!
+//! Data is encoded in byte mode at the smallest version, 1 to 40, that holds
+//! it at the error correction level asked for. Encoding needs no heap: the
+//! caller provides two buffers of `buffer_len(version)` bytes for the
+//! largest version it allows, one for the symbol and one for scratch.
+//! `MAX_BUFFER_LEN` allows every version.
+
+use super::{Canvas, Color};
+
+pub const MIN_VERSION: u8 = 1;
+pub const MAX_VERSION: u8 = 40;
+
+/// Modules of quiet zone on each side recommended by the standard.
+pub const QUIET_ZONE: u16 = 4;
+
+/// Length of each buffer needed to encode a code of `version`.
+pub const fn buffer_len(version: u8) -> usize {
+    let size = symbol_size(version);
+    (size * size + 7) / 8
+}
+
+/// Length of each buffer needed to encode a code of any version.
+pub const MAX_BUFFER_LEN: usize = buffer_len(MAX_VERSION);
+
+const fn symbol_size(version: u8) -> usize {
+    version as usize * 4 + 17
+}
+
+/// How much of the code can be lost and still be read.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EccLevel {
+    /// About 7%.
+    Low,
+    /// About 15%.
+    Medium,
+    /// About 25%.
+    Quartile,
+    /// About 30%.
+    High,
+}
+
+impl EccLevel {
+    fn index(self) -> usize {
+        match self {
+            EccLevel::Low => 0,
+            EccLevel::Medium => 1,
+            EccLevel::Quartile => 2,
+            EccLevel::High => 3,
+        }
+    }
+
+    /// The two bits in the format information.
+    fn format_bits(self) -> u16 {
+        match self {
+            EccLevel::Low => 1,
+            EccLevel::Medium => 0,
+            EccLevel::Quartile => 3,
+            EccLevel::High => 2,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QrError {
+    /// The data does not fit in a version 40 code at the level asked for.
+    LengthError,
+    /// The buffers are too short for the version the data needs.
+    BufferError,
+    /// The code does not fit on the canvas at one pixel per module.
+    SizeError,
+}
+
+/// Error correction codewords in each block, by level and version.
+const ECC_CODEWORDS_PER_BLOCK: [[u8; 41]; 4] = [
+    [0, 7, 10, 15, 20, 26, 18, 20, 24, 30, 18, 20, 24, 26, 30, 22, 24, 28, 30, 28, 28,
+        28, 28, 30, 30, 26, 28, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30],
+    [0, 10, 16, 26, 18, 24, 16, 18, 22, 22, 26, 30, 22, 22, 24, 24, 28, 28, 26, 26, 26,
+        26, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28],
+    [0, 13, 22, 18, 26, 18, 24, 18, 22, 20, 24, 28, 26, 24, 20, 30, 24, 28, 28, 26, 30,
+        28, 30, 30, 30, 30, 28, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30],
+    [0, 17, 28, 22, 16, 22, 28, 26, 26, 24, 28, 24, 28, 22, 24, 24, 30, 28, 28, 26, 28,
+        30, 24, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30],
+];
+
+/// Error correction blocks, by level and version.
+const ECC_BLOCKS: [[u8; 41]; 4] = [
+    [0, 1, 1, 1, 1, 1, 2, 2, 2, 2, 4, 4, 4, 4, 4, 6, 6, 6, 6, 7, 8,
+        8, 9, 9, 10, 12, 12, 12, 13, 14, 15, 16, 17, 18, 19, 19, 20, 21, 22, 24, 25],
+    [0, 1, 1, 1, 2, 2, 4, 4, 4, 5, 5, 5, 8, 9, 9, 10, 10, 11, 13, 14, 16,
+        17, 17, 18, 20, 21, 23, 25, 26, 28, 29, 31, 33, 35, 37, 38, 40, 43, 45, 47, 49],
+    [0, 1, 1, 2, 2, 4, 4, 6, 6, 8, 8, 8, 10, 12, 16, 12, 17, 16, 18, 21, 20,
+        23, 23, 25, 27, 29, 34, 34, 35, 38, 40, 43, 45, 48, 51, 53, 56, 59, 62, 65, 68],
+    [0, 1, 1, 2, 4, 4, 4, 5, 6, 8, 8, 11, 11, 16, 16, 18, 16, 19, 21, 25, 25,
+        25, 34, 30, 32, 35, 37, 40, 42, 45, 48, 51, 54, 57, 60, 63, 66, 70, 74, 77, 81],
+];
+
+const MAX_ECC_CODEWORDS: usize = 30;
+const MAX_ALIGNMENT_PATTERNS: usize = 7;
+
+/// Modules of a version that hold codewords rather than function patterns.
+fn raw_data_modules(version: u8) -> usize {
+    let v = version as usize;
+    let mut modules = (16 * v + 128) * v + 64;
+    if v >= 2 {
+        let align = v / 7 + 2;
+        modules -= (25 * align - 10) * align - 55;
+        if v >= 7 {
+            modules -= 36;
+        }
+    }
+    modules
+}
+
+fn data_codewords(version: u8, ecc: EccLevel) -> usize {
+    let (l, v) = (ecc.index(), version as usize);
+    let ecc_codewords = ECC_CODEWORDS_PER_BLOCK[l][v] as usize * ECC_BLOCKS[l][v] as usize;
+    raw_data_modules(version) / 8 - ecc_codewords
+}
+
+/// Bits of the character count of byte mode data.
+fn count_bits(version: u8) -> usize {
+    if version <= 9 { 8 } else { 16 }
+}
+
+/// Centers of the alignment patterns on each axis, returning how many.
+fn alignment_positions(version: u8, positions: &mut [u8; MAX_ALIGNMENT_PATTERNS]) -> usize {
+    if version == 1 {
+        return 0;
+    }
+    let v = version as usize;
+    let count = v / 7 + 2;
+    let step = (v * 8 + count * 3 + 5) / (count * 4 - 4) * 2;
+    positions[0] = 6;
+    for i in 1..count {
+        positions[count - i] = (v * 4 + 10 - (i - 1) * step) as u8;
+    }
+    count
+}
+
+/// Whether alignment pattern (`i`, `j`) of `count` on each axis is in a
+/// corner taken by a finder pattern.
+fn is_finder_corner(i: usize, j: usize, count: usize) -> bool {
+    i == 0 && (j == 0 || j == count - 1) || i == count - 1 && j == 0
+}
+
+/// A QR code symbol, one bit per module, held in the caller's buffer.
+pub struct QrCode<'a> {
+    modules: &'a [u8],
+    version: u8,
+}
+
+impl<'a> QrCode<'a> {
+    /// Encodes `data` at the smallest version that fits, with the mask of
+    /// lowest penalty.
+    ///
+    /// Both buffers must hold `buffer_len` of that version, or
+    /// `QrError::BufferError` is returned; `MAX_BUFFER_LEN` fits any.
+    pub fn encode(
+        data: &[u8],
+        ecc: EccLevel,
+        buf: &'a mut [u8],
+        scratch: &mut [u8],
+    ) -> Result<QrCode<'a>, QrError> {
+        let version = (MIN_VERSION..=MAX_VERSION)
+            .find(|&v| 4 + count_bits(v) + data.len() * 8 <= data_codewords(v, ecc) * 8)
+            .ok_or(QrError::LengthError)?;
+        let len = buffer_len(version);
+        if buf.len() < len || scratch.len() < len {
+            return Err(QrError::BufferError);
+        }
+        let buf = &mut buf[..len];
+        let scratch = &mut scratch[..len];
+
+        let data_len = data_codewords(version, ecc);
+        write_data(data, version, &mut buf[..data_len]);
+        add_ecc_and_interleave(&buf[..data_len], version, ecc, scratch);
+
+        let mut symbol = Symbol { modules: buf, size: symbol_size(version) };
+        symbol.clear();
+        symbol.mark_function_modules(version);
+        symbol.draw_codewords(&scratch[..raw_data_modules(version) / 8]);
+        symbol.draw_light_function_modules(version);
+
+        let mut functions = Symbol { modules: scratch, size: symbol_size(version) };
+        functions.clear();
+        functions.mark_function_modules(version);
+
+        let mut best = (0, u32::MAX);
+        for mask in 0..8 {
+            symbol.apply_mask(&functions, mask);
+            symbol.draw_format_bits(ecc, mask);
+            let penalty = symbol.penalty();
+            if penalty < best.1 {
+                best = (mask, penalty);
+            }
+            symbol.apply_mask(&functions, mask);
+        }
+        symbol.apply_mask(&functions, best.0);
+        symbol.draw_format_bits(ecc, best.0);
+
+        Ok(QrCode { modules: symbol.modules, version })
+    }
+
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    /// Modules on each side, not counting the quiet zone.
+    pub fn size(&self) -> u16 {
+        symbol_size(self.version) as u16
+    }
+
+    /// Whether the module at (`x`, `y`) is dark. Modules outside the code
+    /// are light.
+    pub fn module(&self, x: u16, y: u16) -> bool {
+        let size = self.size();
+        x < size && y < size && get(self.modules, y as usize * size as usize + x as usize)
+    }
+
+    /// The largest whole number of pixels per module at which the code and
+    /// `quiet_zone` modules around it fit in `width` by `height` pixels.
+    pub fn scale_for(&self, width: u16, height: u16, quiet_zone: u16) -> u16 {
+        let modules = self.size() as u32 + 2 * quiet_zone as u32;
+        (width.min(height) as u32 / modules) as u16
+    }
+
+    /// Draws the code as large as fits on `canvas`, centered, with
+    /// `quiet_zone` modules of white around it. Returns the pixels per module.
+    pub fn draw(&self, canvas: &mut Canvas, quiet_zone: u16) -> Result<u16, QrError> {
+        let scale = self.scale_for(canvas.width(), canvas.height(), quiet_zone);
+        if scale == 0 {
+            return Err(QrError::SizeError);
+        }
+
+        let size = self.size();
+        let side = (size + 2 * quiet_zone) * scale;
+        let left = (canvas.width() - side) / 2;
+        let top = (canvas.height() - side) / 2;
+        canvas.fill_rect(left, top, side, side, Color::White);
+
+        let left = left + quiet_zone * scale;
+        let top = top + quiet_zone * scale;
+        for y in 0..size {
+            let y_pixel = top + y * scale;
+            // skip rows outside a band
+            if y_pixel + scale <= canvas.top() || y_pixel >= canvas.top() + canvas.rows() {
+                continue;
+            }
+            for x in 0..size {
+                if self.module(x, y) {
+                    canvas.fill_rect(left + x * scale, y_pixel, scale, scale, Color::Black);
+                }
+            }
+        }
+
+        Ok(scale)
+    }
+}
+
+fn get(bits: &[u8], i: usize) -> bool {
+    bits[i / 8] & (1 << (i % 8)) != 0
+}
+
+/// Writes the byte mode segment, terminator, and padding.
+fn write_data(data: &[u8], version: u8, out: &mut [u8]) {
+    for b in out.iter_mut() {
+        *b = 0;
+    }
+    let capacity = out.len() * 8;
+    let mut bits = BitWriter { out, bit: 0 };
+
+    bits.push(0b0100, 4);
+    bits.push(data.len() as u32, count_bits(version));
+    for &b in data {
+        bits.push(b as u32, 8);
+    }
+
+    let terminator = (capacity - bits.bit).min(4);
+    bits.push(0, terminator);
+    let padding = (8 - bits.bit % 8) % 8;
+    bits.push(0, padding);
+    for &pad in [0xec, 0x11].iter().cycle().take((capacity - bits.bit) / 8) {
+        bits.push(pad, 8);
+    }
+}
+
+struct BitWriter<'a> {
+    out: &'a mut [u8],
+    bit: usize,
+}
+
+impl<'a> BitWriter<'a> {
+    /// Appends the low `bits` bits of `value`, most significant first.
+    fn push(&mut self, value: u32, bits: usize) {
+        for i in (0..bits).rev() {
+            if (value >> i) & 1 != 0 {
+                self.out[self.bit / 8] |= 0x80 >> (self.bit % 8);
+            }
+            self.bit += 1;
+        }
+    }
+}
+
+/// Splits `data` into blocks, adds error correction to each, and
+/// interleaves them into `out`.
+fn add_ecc_and_interleave(data: &[u8], version: u8, ecc: EccLevel, out: &mut [u8]) {
+    let (l, v) = (ecc.index(), version as usize);
+    let blocks = ECC_BLOCKS[l][v] as usize;
+    let ecc_len = ECC_CODEWORDS_PER_BLOCK[l][v] as usize;
+    let raw_codewords = raw_data_modules(version) / 8;
+    let short_blocks = blocks - raw_codewords % blocks;
+    let short_data_len = raw_codewords / blocks - ecc_len;
+
+    let mut divisor = [0; MAX_ECC_CODEWORDS];
+    reed_solomon_divisor(&mut divisor[..ecc_len]);
+
+    let mut start = 0;
+    for i in 0..blocks {
+        let len = short_data_len + if i < short_blocks { 0 } else { 1 };
+        let block = &data[start..start + len];
+        let mut remainder = [0; MAX_ECC_CODEWORDS];
+        reed_solomon_remainder(block, &divisor[..ecc_len], &mut remainder[..ecc_len]);
+
+        // the extra codeword of long blocks comes after those all blocks have
+        let mut k = i;
+        for (j, &b) in block.iter().enumerate() {
+            if j == short_data_len {
+                k -= short_blocks;
+            }
+            out[k] = b;
+            k += blocks;
+        }
+        for (j, &b) in remainder[..ecc_len].iter().enumerate() {
+            out[data.len() + i + j * blocks] = b;
+        }
+        start += len;
+    }
+}
+
+/// Multiplication in GF(2^8) modulo x^8 + x^4 + x^3 + x^2 + 1.
+fn gf_mul(x: u8, y: u8) -> u8 {
+    let mut z: u16 = 0;
+    for i in (0..8).rev() {
+        z = (z << 1) ^ ((z >> 7) * 0x11d);
+        z ^= ((y as u16 >> i) & 1) * x as u16;
+    }
+    z as u8
+}
+
+/// The generator polynomial of degree `divisor.len()`, highest term first
+/// and the leading 1 left out.
+fn reed_solomon_divisor(divisor: &mut [u8]) {
+    let degree = divisor.len();
+    for d in divisor.iter_mut() {
+        *d = 0;
+    }
+    divisor[degree - 1] = 1;
+
+    let mut root = 1;
+    for _ in 0..degree {
+        for j in 0..degree {
+            divisor[j] = gf_mul(divisor[j], root);
+            if j + 1 < degree {
+                divisor[j] ^= divisor[j + 1];
+            }
+        }
+        root = gf_mul(root, 0x02);
+    }
+}
+
+fn reed_solomon_remainder(data: &[u8], divisor: &[u8], remainder: &mut [u8]) {
+    for r in remainder.iter_mut() {
+        *r = 0;
+    }
+    for &b in data {
+        let factor = b ^ remainder[0];
+        remainder.copy_within(1.., 0);
+        let last = remainder.len() - 1;
+        remainder[last] = 0;
+        for (r, &d) in remainder.iter_mut().zip(divisor) {
+            *r ^= gf_mul(d, factor);
+        }
+    }
+}
+
+/// A square of modules being built, one bit per module, row by row.
+struct Symbol<'a> {
+    modules: &'a mut [u8],
+    size: usize,
+}
+
+impl<'a> Symbol<'a> {
+    fn clear(&mut self) {
+        for b in self.modules.iter_mut() {
+            *b = 0;
+        }
+    }
+
+    fn get(&self, x: usize, y: usize) -> bool {
+        get(self.modules, y * self.size + x)
+    }
+
+    fn set(&mut self, x: usize, y: usize, dark: bool) {
+        let i = y * self.size + x;
+        if dark {
+            self.modules[i / 8] |= 1 << (i % 8);
+        } else {
+            self.modules[i / 8] &= !(1 << (i % 8));
+        }
+    }
+
+    fn fill_rect(&mut self, x: usize, y: usize, w: usize, h: usize) {
+        for y in y..y + h {
+            for x in x..x + w {
+                self.set(x, y, true);
+            }
+        }
+    }
+
+    /// Sets every function module dark and leaves the rest as they are.
+    fn mark_function_modules(&mut self, version: u8) {
+        let size = self.size;
+
+        // timing patterns
+        self.fill_rect(6, 0, 1, size);
+        self.fill_rect(0, 6, size, 1);
+
+        // finder patterns, separators, and format information
+        self.fill_rect(0, 0, 9, 9);
+        self.fill_rect(size - 8, 0, 8, 9);
+        self.fill_rect(0, size - 8, 9, 8);
+
+        let mut positions = [0; MAX_ALIGNMENT_PATTERNS];
+        let count = alignment_positions(version, &mut positions);
+        for i in 0..count {
+            for j in 0..count {
+                if is_finder_corner(i, j, count) {
+                    continue;
+                }
+                self.fill_rect(positions[i] as usize - 2, positions[j] as usize - 2, 5, 5);
+            }
+        }
+
+        if version >= 7 {
+            self.fill_rect(size - 11, 0, 3, 6);
+            self.fill_rect(0, size - 11, 6, 3);
+        }
+    }
+
+    /// Places codewords in the zigzag order, skipping the function modules,
+    /// which must be marked dark while the rest are light.
+    fn draw_codewords(&mut self, codewords: &[u8]) {
+        let size = self.size;
+        let bits = codewords.len() * 8;
+        let mut i = 0;
+
+        let mut right = size - 1;
+        while right >= 1 {
+            // the vertical timing pattern has no column pair of its own
+            if right == 6 {
+                right = 5;
+            }
+            let upward = (right + 1) & 2 == 0;
+            for vert in 0..size {
+                let y = if upward { size - 1 - vert } else { vert };
+                for x in [right, right - 1].iter().cloned() {
+                    if !self.get(x, y) && i < bits {
+                        let dark = codewords[i / 8] & (0x80 >> (i % 8)) != 0;
+                        self.set(x, y, dark);
+                        i += 1;
+                    }
+                }
+            }
+            if right < 2 {
+                break;
+            }
+            right -= 2;
+        }
+    }
+
+    /// Draws the light modules of the function patterns, which were marked
+    /// dark.
+    fn draw_light_function_modules(&mut self, version: u8) {
+        let size = self.size;
+
+        for i in (7..size - 7).step_by(2) {
+            self.set(6, i, false);
+            self.set(i, 6, false);
+        }
+
+        for dy in -4i32..=4 {
+            for dx in -4i32..=4 {
+                let distance = dx.abs().max(dy.abs());
+                if distance != 2 && distance != 4 {
+                    continue;
+                }
+                let corners = [(3, 3), (size as i32 - 4, 3), (3, size as i32 - 4)];
+                for &(cx, cy) in corners.iter() {
+                    let (x, y) = (cx + dx, cy + dy);
+                    if x >= 0 && y >= 0 && x < size as i32 && y < size as i32 {
+                        self.set(x as usize, y as usize, false);
+                    }
+                }
+            }
+        }
+
+        let mut positions = [0; MAX_ALIGNMENT_PATTERNS];
+        let count = alignment_positions(version, &mut positions);
+        for i in 0..count {
+            for j in 0..count {
+                if is_finder_corner(i, j, count) {
+                    continue;
+                }
+                for dy in 0..3 {
+                    for dx in 0..3 {
+                        if dx != 1 || dy != 1 {
+                            let x = positions[i] as usize - 1 + dx;
+                            let y = positions[j] as usize - 1 + dy;
+                            self.set(x, y, false);
+                        }
+                    }
+                }
+            }
+        }
+
+        if version >= 7 {
+            let mut rem = version as u32;
+            for _ in 0..12 {
+                rem = (rem << 1) ^ ((rem >> 11) * 0x1f25);
+            }
+            let bits = (version as u32) << 12 | rem;
+            for i in 0..18 {
+                if (bits >> i) & 1 == 0 {
+                    let (a, b) = (size - 11 + i % 3, i / 3);
+                    self.set(a, b, false);
+                    self.set(b, a, false);
+                }
+            }
+        }
+    }
+
+    /// Both copies of the format information, and the dark module.
+    fn draw_format_bits(&mut self, ecc: EccLevel, mask: u8) {
+        let size = self.size;
+        let data = ecc.format_bits() << 3 | mask as u16;
+        let mut rem = data;
+        for _ in 0..10 {
+            rem = (rem << 1) ^ ((rem >> 9) * 0x537);
+        }
+        let bits = (data << 10 | rem) ^ 0x5412;
+        let bit = |i: usize| (bits >> i) & 1 != 0;
+
+        for i in 0..6 {
+            self.set(8, i, bit(i));
+        }
+        self.set(8, 7, bit(6));
+        self.set(8, 8, bit(7));
+        self.set(7, 8, bit(8));
+        for i in 9..15 {
+            self.set(14 - i, 8, bit(i));
+        }
+
+        for i in 0..8 {
+            self.set(size - 1 - i, 8, bit(i));
+        }
+        for i in 8..15 {
+            self.set(8, size - 15 + i, bit(i));
+        }
+        self.set(8, size - 8, true);
+    }
+
+    /// Inverts the modules `mask` selects that are not marked in `functions`.
+    /// Applying the same mask again undoes it.
+    fn apply_mask(&mut self, functions: &Symbol, mask: u8) {
+        for y in 0..self.size {
+            for x in 0..self.size {
+                if functions.get(x, y) {
+                    continue;
+                }
+                let invert = match mask {
+                    0 => (x + y) % 2 == 0,
+                    1 => y % 2 == 0,
+                    2 => x % 3 == 0,
+                    3 => (x + y) % 3 == 0,
+                    4 => (x / 3 + y / 2) % 2 == 0,
+                    5 => x * y % 2 + x * y % 3 == 0,
+                    6 => (x * y % 2 + x * y % 3) % 2 == 0,
+                    _ => ((x + y) % 2 + x * y % 3) % 2 == 0,
+                };
+                if invert {
+                    let dark = self.get(x, y);
+                    self.set(x, y, !dark);
+                }
+            }
+        }
+    }
+
+    /// The penalty score of the standard, lower being easier to read.
+    fn penalty(&self) -> u32 {
+        let size = self.size;
+        let mut penalty = 0;
+
+        for transpose in [false, true].iter().cloned() {
+            let get = |a: usize, b: usize| if transpose { self.get(b, a) } else { self.get(a, b) };
+            for line in 0..size {
+                // runs of five or more of a color, and finder-like
+                // patterns: runs in the ratio 1:1:3:1:1 from the dark, with
+                // four times the unit of light on one side and at least the
+                // unit on the other, the outside of the symbol counting as
+                // light
+                let mut history = [0; 7];
+                let mut dark = false;
+                let mut run = 0;
+                for i in 0..size {
+                    if get(i, line) == dark {
+                        run += 1;
+                        continue;
+                    }
+                    penalty += run_penalty(run);
+                    push_run(&mut history, run, size);
+                    if !dark {
+                        penalty += 40 * finder_like(&history);
+                    }
+                    dark = !dark;
+                    run = 1;
+                }
+                penalty += run_penalty(run);
+                if dark {
+                    push_run(&mut history, run, size);
+                    run = 0;
+                }
+                push_run(&mut history, run + size, size);
+                penalty += 40 * finder_like(&history);
+            }
+        }
+
+        // 2 by 2 blocks of a color
+        for y in 0..size - 1 {
+            for x in 0..size - 1 {
+                let dark = self.get(x, y);
+                if dark == self.get(x + 1, y) && dark == self.get(x, y + 1)
+                    && dark == self.get(x + 1, y + 1)
+                {
+                    penalty += 3;
+                }
+            }
+        }
+
+        // balance of dark and light
+        let total = (size * size) as i64;
+        let dark = (0..size * size).filter(|&i| get(self.modules, i)).count() as i64;
+        let k = ((dark * 20 - total * 10).abs() + total - 1) / total - 1;
+        penalty + k as u32 * 10
+    }
+}
+
+/// The penalty for a run of `run` modules of a color.
+fn run_penalty(run: usize) -> u32 {
+    if run >= 5 {
+        3 + (run - 5) as u32
+    } else {
+        0
+    }
+}
+
+/// Adds a run to the lengths of the last seven runs of a line, most recent
+/// first. The first run of a line is light and includes the `size` light
+/// modules counted outside the symbol.
+fn push_run(history: &mut [usize; 7], mut run: usize, size: usize) {
+    if history[0] == 0 {
+        run += size;
+    }
+    history.copy_within(0..6, 1);
+    history[0] = run;
+}
+
+/// Finder-like patterns ending at the last light run in `history`.
+fn finder_like(history: &[usize; 7]) -> u32 {
+    let n = history[1];
+    let core = n > 0
+        && history[2] == n && history[3] == n * 3 && history[4] == n && history[5] == n;
+    (core && history[0] >= n * 4 && history[6] >= n) as u32
+        + (core && history[6] >= n * 4 && history[0] >= n) as u32
+}
@@ -0,0 +1,140 @@
+//! Choosing between partial updates and full refreshes.
+//!
+//! Partial updates are quick but leave ghosting that builds up with each
+//! one. A `RefreshTracker` counts what has happened since the last full
+//! refresh and promotes an update to a full refresh once a limit in its
+//! `RefreshPolicy` is reached.
+
+/// Limits on partial updates, past which the next update is promoted to a
+/// full refresh to clear ghosting. A limit of `None` is never reached.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RefreshPolicy {
+    /// Partial updates between full refreshes.
+    pub max_partial_updates: Option<u16>,
+    /// Milliseconds since the last full refresh.
+    pub max_elapsed_ms: Option<u32>,
+    /// Pixels changed by partial updates since the last full refresh.
+    pub max_changed_pixels: Option<u32>,
+}
+
+impl RefreshPolicy {
+    /// Every update is a full refresh.
+    pub const ALWAYS_FULL: RefreshPolicy = RefreshPolicy {
+        max_partial_updates: Some(0),
+        max_elapsed_ms: None,
+        max_changed_pixels: None,
+    };
+
+    /// Updates are only full when forced.
+    pub const NEVER_FULL: RefreshPolicy = RefreshPolicy {
+        max_partial_updates: None,
+        max_elapsed_ms: None,
+        max_changed_pixels: None,
+    };
+}
+
+/// A full refresh every 10 partial updates or 3 minutes.
+impl Default for RefreshPolicy {
+    fn default() -> RefreshPolicy {
+        RefreshPolicy {
+            max_partial_updates: Some(10),
+            max_elapsed_ms: Some(3 * 60 * 1000),
+            max_changed_pixels: None,
+        }
+    }
+}
+
+/// The kind of update.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Refresh {
+    Partial,
+    Full,
+}
+
+/// Tracks partial updates since the last full refresh against a
+/// `RefreshPolicy`. Used with `Screen::refresh`.
+///
+/// There is no clock; time is counted by calling `tick`.
+#[derive(Clone, Copy, Debug)]
+pub struct RefreshTracker {
+    pub policy: RefreshPolicy,
+    partial_updates: u16,
+    elapsed_ms: u32,
+    changed_pixels: u32,
+    force_full: bool,
+}
+
+impl RefreshTracker {
+    /// The first update is a full refresh, as the state of the panel is
+    /// unknown.
+    pub fn new(policy: RefreshPolicy) -> RefreshTracker {
+        RefreshTracker {
+            policy,
+            partial_updates: 0,
+            elapsed_ms: 0,
+            changed_pixels: 0,
+            force_full: true,
+        }
+    }
+
+    pub fn partial_updates(&self) -> u16 {
+        self.partial_updates
+    }
+
+    pub fn elapsed_ms(&self) -> u32 {
+        self.elapsed_ms
+    }
+
+    pub fn changed_pixels(&self) -> u32 {
+        self.changed_pixels
+    }
+
+    /// Counts `ms` milliseconds as passed.
+    pub fn tick(&mut self, ms: u32) {
+        self.elapsed_ms = self.elapsed_ms.saturating_add(ms);
+    }
+
+    /// Makes the next update a full refresh.
+    pub fn force_full(&mut self) {
+        self.force_full = true;
+    }
+
+    /// The kind of the next update, which changes `changed_pixels` pixels.
+    pub fn next(&self, changed_pixels: u32) -> Refresh {
+        let over = |limit: Option<u32>, value: u32| limit.map_or(false, |limit| value > limit);
+
+        let partial_updates = self.partial_updates as u32 + 1;
+        let changed_pixels = self.changed_pixels.saturating_add(changed_pixels);
+        if self.force_full
+            || over(self.policy.max_partial_updates.map(u32::from), partial_updates)
+            || over(self.policy.max_elapsed_ms, self.elapsed_ms)
+            || over(self.policy.max_changed_pixels, changed_pixels)
+        {
+            Refresh::Full
+        } else {
+            Refresh::Partial
+        }
+    }
+
+    /// Counts an update as done.
+    pub fn record(&mut self, refresh: Refresh, changed_pixels: u32) {
+        match refresh {
+            Refresh::Full => {
+                self.partial_updates = 0;
+                self.elapsed_ms = 0;
+                self.changed_pixels = 0;
+                self.force_full = false;
+            }
+            Refresh::Partial => {
+                self.partial_updates = self.partial_updates.saturating_add(1);
+                self.changed_pixels = self.changed_pixels.saturating_add(changed_pixels);
+            }
+        }
+    }
+}
+
+impl Default for RefreshTracker {
+    fn default() -> RefreshTracker {
+        RefreshTracker::new(RefreshPolicy::default())
+    }
+}
@@ -0,0 +1,139 @@
+//! A copy of panel RAM kept by the caller, for updates that are not aligned
+//! to bytes.
+//!
+//! RAM can only be written a byte, 8 pixels, at a time. `Screen::load_pixels`
+//! and `Screen::update_pixels` send the bytes enclosing a rectangle of pixels
+//! from a `Shadow` with the new pixels merged in, so the pixels sharing those
+//! bytes keep their values.
+
+use super::{image_len, width_pixels_to_bytes, Canvas, ImageSource, Window};
+
+/// A copy of the image in panel RAM, held in a caller provided buffer, so
+/// updates that do not start or end on a byte can be merged with the pixels
+/// around them. See `Screen::load_pixels`.
+///
+/// Rows run from the top of the screen, packed the same as images given to
+/// `Screen::show_full_screen_image`.
+pub struct Shadow<'a> {
+    buf: &'a mut [u8],
+    width: u16,
+    height: u16,
+}
+
+impl<'a> Shadow<'a> {
+    /// `buf` must be `image_len(width, height)` bytes and hold what is in
+    /// panel RAM, e.g. the last full screen image shown. Returns `None` if it
+    /// is the wrong length.
+    pub fn new(buf: &'a mut [u8], width: u16, height: u16) -> Option<Shadow<'a>> {
+        if buf.len() != image_len(width, height) {
+            return None;
+        }
+
+        Some(Shadow { buf, width, height })
+    }
+
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        self.buf
+    }
+
+    /// For changing the copy without writing to the panel, e.g. before
+    /// showing it as a full screen image.
+    pub fn as_mut_bytes(&mut self) -> &mut [u8] {
+        self.buf
+    }
+
+    pub fn canvas(&mut self) -> Canvas<'_> {
+        Canvas::new(self.buf, self.width)
+    }
+
+    /// The bytes enclosing `window`, which must fit and not be empty, with
+    /// `image` merged in as by `merge`.
+    pub(crate) fn merged<'b>(&'b self, window: &Window, image: &'b [u8]) -> Merged<'b> {
+        Merged { buf: self.buf, width: self.width, window: *window, image }
+    }
+
+    /// Copies `image`, `window.w` by `window.h` pixels packed with rows
+    /// starting on a byte, into `window`, which must fit and not be empty.
+    pub(crate) fn merge(&mut self, window: &Window, image: &[u8]) {
+        let row_bytes = width_pixels_to_bytes(self.width) as usize;
+
+        for row in 0..window.h as usize {
+            let dst_row = (window.y as usize + row) * row_bytes;
+            for x_byte in enclosing_bytes(window) {
+                let b = merged_byte(self.buf, self.width, window, image, x_byte, row);
+                self.buf[dst_row + x_byte] = b;
+            }
+        }
+    }
+}
+
+/// See `Shadow::merged`.
+pub(crate) struct Merged<'b> {
+    buf: &'b [u8],
+    width: u16,
+    window: Window,
+    image: &'b [u8],
+}
+
+impl<'b> ImageSource for Merged<'b> {
+    fn len(&self) -> usize {
+        enclosing_bytes(&self.window).len() * self.window.h as usize
+    }
+
+    fn stream<E, F>(&mut self, mut sink: F) -> Result<(), E>
+    where
+        F: FnMut(u8) -> Result<(), E>,
+    {
+        for row in 0..self.window.h as usize {
+            for x_byte in enclosing_bytes(&self.window) {
+                sink(merged_byte(self.buf, self.width, &self.window, self.image, x_byte, row))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Columns of the bytes holding the pixels of `window`.
+fn enclosing_bytes(window: &Window) -> core::ops::Range<usize> {
+    window.x as usize / 8..(window.x as usize + window.w as usize + 7) / 8
+}
+
+/// Byte `x_byte` of row `row` of `window` in `buf`, a full screen image
+/// `width` pixels wide, with the pixels of `image` in the window replacing
+/// its own.
+fn merged_byte(
+    buf: &[u8],
+    width: u16,
+    window: &Window,
+    image: &[u8],
+    x_byte: usize,
+    row: usize,
+) -> u8 {
+    let row_bytes = width_pixels_to_bytes(width) as usize;
+    let image_row_bytes = width_pixels_to_bytes(window.w) as usize;
+    let (x, w) = (window.x as usize, window.w as usize);
+
+    let mut b = buf[(window.y as usize + row) * row_bytes + x_byte];
+    for bit in 0..8 {
+        let dst_x = x_byte * 8 + bit;
+        if dst_x < x || dst_x >= x + w {
+            continue;
+        }
+        let i = dst_x - x;
+        let mask = 0x80 >> bit;
+        if image[row * image_row_bytes + i / 8] & (0x80 >> (i % 8)) != 0 {
+            b |= mask;
+        } else {
+            b &= !mask;
+        }
+    }
+    b
+}
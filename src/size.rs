@@ -0,0 +1,121 @@
+//! Panel dimensions, chosen at runtime with `DynamicSize` or fixed in the
+//! type with `FixedSize`, so that full screen images of the wrong length fail
+//! to compile. `SizedPreset` ties a preset's builder to its dimensions.
+
+use super::{image_len, Preset, ScreenBuilder};
+
+/// The dimensions of a panel, in pixels.
+pub trait Dimensions {
+    /// A buffer holding a full screen image.
+    type Image: AsRef<[u8]> + ?Sized;
+
+    fn x_size(&self) -> u16;
+    fn y_size(&self) -> u16;
+}
+
+/// Dimensions chosen at runtime. Full screen images are length checked when
+/// they are shown.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DynamicSize {
+    pub x_size: u16,
+    pub y_size: u16,
+}
+
+impl Dimensions for DynamicSize {
+    type Image = [u8];
+
+    fn x_size(&self) -> u16 {
+        self.x_size
+    }
+
+    fn y_size(&self) -> u16 {
+        self.y_size
+    }
+}
+
+/// Dimensions fixed at compile time.
+///
+/// `W` and `H` are the width and height in pixels. `N` is the length in bytes
+/// of a full screen image and must equal `image_len(W, H)`; any other value
+/// fails to compile. Full screen images are `[u8; N]`, so a wrongly sized
+/// image is a type error.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FixedSize<const W: u16, const H: u16, const N: usize>(());
+
+impl<const W: u16, const H: u16, const N: usize> FixedSize<W, H, N> {
+    pub const WIDTH: u16 = W;
+    pub const HEIGHT: u16 = H;
+    /// Length in bytes of a full screen image.
+    pub const LEN: usize = N;
+
+    const VALID: () = assert!(N == image_len(W, H), "N must equal image_len(W, H)");
+
+    pub const fn new() -> Self {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::VALID;
+        FixedSize(())
+    }
+}
+
+impl<const W: u16, const H: u16, const N: usize> Default for FixedSize<W, H, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const W: u16, const H: u16, const N: usize> Dimensions for FixedSize<W, H, N> {
+    type Image = [u8; N];
+
+    fn x_size(&self) -> u16 {
+        W
+    }
+
+    fn y_size(&self) -> u16 {
+        H
+    }
+}
+
+pub type CFAP200200A0_154Size = FixedSize<200, 200, { image_len(200, 200) }>;
+pub type CFAP200200A1_154Size = FixedSize<200, 200, { image_len(200, 200) }>;
+
+/// A panel preset whose dimensions are part of its type.
+///
+/// ```ignore
+/// let screen = CFAP200200A0_154::builder().new_sized_screen(
+///     CFAP200200A0_154::size(), spi, dc, cs, busy, reset, &mut delay,
+/// )?;
+/// ```
+pub trait SizedPreset {
+    type Size: Dimensions + Default;
+
+    /// The builder of the preset, sized to match `Size`.
+    fn builder() -> ScreenBuilder;
+
+    fn size() -> Self::Size {
+        Self::Size::default()
+    }
+}
+
+/// `Preset::CFAP200200A0_154` with its dimensions in the type.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CFAP200200A0_154;
+
+impl SizedPreset for CFAP200200A0_154 {
+    type Size = CFAP200200A0_154Size;
+
+    fn builder() -> ScreenBuilder {
+        ScreenBuilder::preset(Preset::CFAP200200A0_154)
+    }
+}
+
+/// `Preset::CFAP200200A1_154` with its dimensions in the type.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CFAP200200A1_154;
+
+impl SizedPreset for CFAP200200A1_154 {
+    type Size = CFAP200200A1_154Size;
+
+    fn builder() -> ScreenBuilder {
+        ScreenBuilder::preset(Preset::CFAP200200A1_154)
+    }
+}
@@ -0,0 +1,180 @@
+//! Streaming of images to controller RAM a byte at a time.
+//!
+//! `Screen` writes images through `ImageSource`, so they can be generated or
+//! decompressed as they are sent rather than held in memory. `SubImage`
+//! streams a rectangle of a larger image, `Rows` renders a row at a time into
+//! a small buffer, and `from_fn` produces each byte from a closure.
+
+/// Bytes of an image, produced in the order they are written to RAM.
+///
+/// Images do not have to be held in memory as a whole; a source may generate
+/// or decode its bytes as they are streamed to the controller.
+///
+/// Implemented for references to anything that is `AsRef<[u8]>`, so slices,
+/// arrays, and vectors can be passed as `&image`.
+pub trait ImageSource {
+    /// Number of bytes the source produces.
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Passes each byte in order to `sink`, stopping at the first error.
+    ///
+    /// Must pass exactly `len()` bytes unless `sink` fails.
+    fn stream<E, F>(&mut self, sink: F) -> Result<(), E>
+    where
+        F: FnMut(u8) -> Result<(), E>;
+}
+
+impl<T: AsRef<[u8]> + ?Sized> ImageSource for &T {
+    fn len(&self) -> usize {
+        (*self).as_ref().len()
+    }
+
+    fn stream<E, F>(&mut self, mut sink: F) -> Result<(), E>
+    where
+        F: FnMut(u8) -> Result<(), E>,
+    {
+        for &b in (*self).as_ref() {
+            sink(b)?;
+        }
+        Ok(())
+    }
+}
+
+/// Lets a source be passed by reference and reused afterwards.
+impl<I: ImageSource + ?Sized> ImageSource for &mut I {
+    fn len(&self) -> usize {
+        (**self).len()
+    }
+
+    fn stream<E, F>(&mut self, sink: F) -> Result<(), E>
+    where
+        F: FnMut(u8) -> Result<(), E>,
+    {
+        (**self).stream(sink)
+    }
+}
+
+/// Generates each byte from its index. See `from_fn`.
+pub struct FromFn<F> {
+    len: usize,
+    f: F,
+}
+
+/// A source of `len` bytes, where byte `i` is `f(i)`.
+pub fn from_fn<F: FnMut(usize) -> u8>(len: usize, f: F) -> FromFn<F> {
+    FromFn { len, f }
+}
+
+impl<F: FnMut(usize) -> u8> ImageSource for FromFn<F> {
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn stream<E, S>(&mut self, mut sink: S) -> Result<(), E>
+    where
+        S: FnMut(u8) -> Result<(), E>,
+    {
+        for i in 0..self.len {
+            sink((self.f)(i))?;
+        }
+        Ok(())
+    }
+}
+
+/// Renders a row at a time into a caller provided buffer, which sets the
+/// width in bytes.
+pub struct Rows<'a, F> {
+    buf: &'a mut [u8],
+    rows: u16,
+    render: F,
+}
+
+impl<'a, F: FnMut(u16, &mut [u8])> Rows<'a, F> {
+    /// `render` is called with the index of each row, from 0 to `rows - 1`,
+    /// and fills the buffer with its bytes.
+    pub fn new(buf: &'a mut [u8], rows: u16, render: F) -> Rows<'a, F> {
+        Rows { buf, rows, render }
+    }
+}
+
+impl<'a, F: FnMut(u16, &mut [u8])> ImageSource for Rows<'a, F> {
+    fn len(&self) -> usize {
+        self.buf.len() * self.rows as usize
+    }
+
+    fn stream<E, S>(&mut self, mut sink: S) -> Result<(), E>
+    where
+        S: FnMut(u8) -> Result<(), E>,
+    {
+        for row in 0..self.rows {
+            (self.render)(row, self.buf);
+            for &b in self.buf.iter() {
+                sink(b)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A rectangle of a larger image whose rows are `stride` bytes apart.
+#[derive(Clone, Copy, Debug)]
+pub struct SubImage<'a> {
+    data: &'a [u8],
+    stride: usize,
+    width: usize,
+    height: usize,
+}
+
+impl<'a> SubImage<'a> {
+    /// `x` and `width` are in bytes, `y` and `height` in rows. Returns `None`
+    /// if the rectangle does not fit in `data`.
+    pub fn new(
+        data: &'a [u8],
+        stride: usize,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    ) -> Option<SubImage<'a>> {
+        if x.checked_add(width)? > stride {
+            return None;
+        }
+        let start = y.checked_mul(stride)?.checked_add(x)?;
+        if height > 0 {
+            let end = (height - 1).checked_mul(stride)?.checked_add(start)?.checked_add(width)?;
+            if end > data.len() {
+                return None;
+            }
+        }
+
+        Some(SubImage {
+            data: if height > 0 { &data[start..] } else { &[] },
+            stride,
+            width,
+            height,
+        })
+    }
+}
+
+impl<'a> ImageSource for SubImage<'a> {
+    fn len(&self) -> usize {
+        self.width * self.height
+    }
+
+    fn stream<E, F>(&mut self, mut sink: F) -> Result<(), E>
+    where
+        F: FnMut(u8) -> Result<(), E>,
+    {
+        for row in 0..self.height {
+            let start = row * self.stride;
+            for &b in &self.data[start..start + self.width] {
+                sink(b)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,159 @@
+//! Structured access to the 30-byte waveform LUT written with
+//! `Command::WriteLutRegister`.
+//!
+//! The LUT is made up of 20 phases. The first 20 bytes hold the voltage
+//! selection (VS) for each phase, one byte per phase, with a 2-bit source
+//! voltage for each of the four possible pixel transitions:
+//!
+//! Byte n: {VS[n-00], VS[n-01], VS[n-10], VS[n-11]}
+//!
+//! VS[n-XY] is the voltage driven during phase n on pixels going from old RAM
+//! value X to new RAM value Y.
+//!
+//! The last 10 bytes hold the timing (TP) for each phase as a 4-bit count of
+//! frames, two phases per byte:
+//!
+//! Byte 20 + n: {TP[2n + 1], TP[2n]}
+//!
+//! A phase with a frame count of 0 is skipped.
+
+/// Number of phases in a waveform.
+pub const PHASES: usize = 20;
+
+/// Length in bytes of the LUT register.
+pub const LUT_LEN: usize = 30;
+
+/// Largest frame count that fits in a phase's 4-bit timing field.
+pub const MAX_FRAMES: u8 = 0x0f;
+
+/// Source voltage driven onto a pixel during a phase.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Voltage {
+    /// VS = 0b00
+    Vss = 0b00,
+    /// VS = 0b01
+    Vsh = 0b01,
+    /// VS = 0b10
+    Vsl = 0b10,
+}
+
+impl Voltage {
+    fn from_bits(bits: u8) -> Option<Voltage> {
+        match bits & 0b11 {
+            0b00 => Some(Voltage::Vss),
+            0b01 => Some(Voltage::Vsh),
+            0b10 => Some(Voltage::Vsl),
+            // 0b11 is reserved
+            _ => None,
+        }
+    }
+}
+
+/// A single phase of a waveform.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Phase {
+    /// VS[n-00]
+    pub from_0_to_0: Voltage,
+    /// VS[n-01]
+    pub from_0_to_1: Voltage,
+    /// VS[n-10]
+    pub from_1_to_0: Voltage,
+    /// VS[n-11]
+    pub from_1_to_1: Voltage,
+    /// TP[n], the number of frames the phase is held for. Must be no greater
+    /// than `MAX_FRAMES`.
+    pub frames: u8,
+}
+
+impl Phase {
+    /// A phase that drives nothing and is skipped.
+    pub const SKIP: Phase = Phase {
+        from_0_to_0: Voltage::Vss,
+        from_0_to_1: Voltage::Vss,
+        from_1_to_0: Voltage::Vss,
+        from_1_to_1: Voltage::Vss,
+        frames: 0,
+    };
+
+    fn vs_byte(&self) -> u8 {
+        (self.from_0_to_0 as u8) << 6
+            | (self.from_0_to_1 as u8) << 4
+            | (self.from_1_to_0 as u8) << 2
+            | self.from_1_to_1 as u8
+    }
+}
+
+impl Default for Phase {
+    fn default() -> Phase {
+        Phase::SKIP
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WaveformError {
+    /// The phase at the given index uses the reserved VS value 0b11.
+    ReservedVoltage(usize),
+    /// The phase at the given index has a frame count greater than
+    /// `MAX_FRAMES`.
+    FramesOutOfRange(usize),
+}
+
+/// A decoded waveform LUT.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Waveform {
+    pub phases: [Phase; PHASES],
+}
+
+impl Waveform {
+    /// Decodes a LUT in the format expected by `Command::WriteLutRegister`.
+    pub fn decode(lut: &[u8; LUT_LEN]) -> Result<Waveform, WaveformError> {
+        let mut phases = [Phase::SKIP; PHASES];
+
+        for (i, phase) in phases.iter_mut().enumerate() {
+            let vs = lut[i];
+            let voltage = |shift: u8| {
+                Voltage::from_bits(vs >> shift).ok_or(WaveformError::ReservedVoltage(i))
+            };
+
+            *phase = Phase {
+                from_0_to_0: voltage(6)?,
+                from_0_to_1: voltage(4)?,
+                from_1_to_0: voltage(2)?,
+                from_1_to_1: voltage(0)?,
+                frames: (lut[PHASES + i / 2] >> ((i % 2) * 4)) & MAX_FRAMES,
+            };
+        }
+
+        Ok(Waveform { phases })
+    }
+
+    /// Encodes the waveform into the format expected by
+    /// `Command::WriteLutRegister`.
+    pub fn encode(&self) -> Result<[u8; LUT_LEN], WaveformError> {
+        let mut lut = [0; LUT_LEN];
+
+        for (i, phase) in self.phases.iter().enumerate() {
+            if phase.frames > MAX_FRAMES {
+                return Err(WaveformError::FramesOutOfRange(i));
+            }
+
+            lut[i] = phase.vs_byte();
+            lut[PHASES + i / 2] |= phase.frames << ((i % 2) * 4);
+        }
+
+        Ok(lut)
+    }
+
+    /// Total number of frames the waveform drives the panel for.
+    pub fn total_frames(&self) -> u16 {
+        self.phases.iter().map(|p| p.frames as u16).sum()
+    }
+}
+
+impl Default for Waveform {
+    fn default() -> Waveform {
+        Waveform {
+            phases: [Phase::SKIP; PHASES],
+        }
+    }
+}
@@ -0,0 +1,107 @@
+extern crate cfap_screen;
+extern crate embedded_graphics_core;
+
+use cfap_screen::emulator::Emulator;
+use cfap_screen::*;
+use embedded_graphics_core::draw_target::DrawTarget;
+use embedded_graphics_core::geometry::{Point, Size};
+use embedded_graphics_core::pixelcolor::BinaryColor;
+use embedded_graphics_core::primitives::Rectangle;
+
+#[test]
+fn canvas_pixels() {
+    let mut buf = [0xff; 6];
+    let mut canvas = Canvas::new(&mut buf, 12);
+
+    assert_eq!(canvas.rows(), 3);
+    canvas.set_pixel(0, 0, Color::Black);
+    canvas.set_pixel(9, 2, Color::Black);
+    canvas.set_pixel(12, 0, Color::Black);
+    canvas.set_pixel(0, 3, Color::Black);
+
+    assert_eq!(canvas.pixel(9, 2), Some(Color::Black));
+    assert_eq!(canvas.pixel(12, 0), None);
+    assert_eq!(canvas.as_bytes(), &[0x7f, 0xff, 0xff, 0xff, 0xff, 0xbf]);
+}
+
+#[test]
+fn band_ignores_rows_it_does_not_hold() {
+    let mut buf = [0xff; 4];
+    let mut band = Canvas::new_band(&mut buf, 8, 10, 8);
+
+    assert_eq!(band.rows(), 2);
+    band.fill_rect(0, 7, 8, 2, Color::Black);
+
+    assert_eq!(band.as_bytes(), &[0x00, 0xff]);
+}
+
+#[test]
+fn bands_draw_every_row() {
+    let mut buf = [0; 3];
+    let mut tops = Vec::new();
+    let mut image = Vec::new();
+
+    {
+        let mut bands = Bands::new(&mut buf, 8, 5, |band: &mut Canvas| {
+            tops.push((band.top(), band.rows()));
+            band.set_pixel(0, 4, Color::Black);
+        })
+        .unwrap();
+        assert_eq!(bands.len(), 5);
+        bands
+            .stream(|b| {
+                image.push(b);
+                Ok::<(), ()>(())
+            })
+            .unwrap();
+    }
+
+    assert_eq!(tops, vec![(0, 3), (3, 2)]);
+    assert_eq!(image, vec![0xff, 0xff, 0xff, 0xff, 0x7f]);
+}
+
+#[test]
+fn render_full_with_small_band() {
+    let (emulator, mut screen) =
+        Emulator::screen(ScreenBuilder::preset(Preset::CFAP200200A0_154)).unwrap();
+    let mut buf = [0; 25 * 16];
+
+    screen
+        .render_full(&mut buf, |band| {
+            let area = Rectangle::new(Point::new(8, 100), Size::new(16, 50));
+            band.fill_solid(&area, BinaryColor::On).unwrap();
+        })
+        .unwrap();
+
+    assert_eq!(emulator.updates(), 1);
+    assert!(emulator.pixel(7, 120));
+    assert!(!emulator.pixel(8, 100));
+    assert!(!emulator.pixel(23, 149));
+    assert!(emulator.pixel(8, 150));
+    assert!(emulator.errors().is_empty(), "{:?}", emulator.errors());
+}
+
+#[test]
+fn render_partial() {
+    let (emulator, mut screen) =
+        Emulator::screen(ScreenBuilder::preset(Preset::CFAP200200A0_154)).unwrap();
+    let mut buf = [0; 25 * 7];
+
+    screen.render_partial(&mut buf, |band| band.set_pixel(199, 199, Color::Black)).unwrap();
+
+    assert!(!emulator.pixel(199, 199));
+    assert!(emulator.pixel(198, 199));
+    assert!(emulator.errors().is_empty(), "{:?}", emulator.errors());
+}
+
+#[test]
+fn render_needs_a_row() {
+    let (_emulator, mut screen) =
+        Emulator::screen(ScreenBuilder::preset(Preset::CFAP200200A0_154)).unwrap();
+    let mut buf = [0; 24];
+
+    match screen.render_full(&mut buf, |_| {}) {
+        Err(ScreenError::LengthError) => {}
+        _ => panic!("expected a length error"),
+    }
+}
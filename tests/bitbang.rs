@@ -0,0 +1,173 @@
+extern crate cfap_screen;
+extern crate embedded_hal;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use cfap_screen::bitbang::{frame9, BitBangSpi, NoMiso, ThreeWire};
+use cfap_screen::mock::Recorder;
+use cfap_screen::params::Vcom;
+use cfap_screen::*;
+use embedded_hal::blocking::delay::DelayUs;
+#[allow(deprecated)]
+use embedded_hal::digital::{InputPin, OutputPin};
+use embedded_hal::spi::FullDuplex;
+
+#[derive(Default)]
+struct Bus {
+    sck: bool,
+    mosi: bool,
+    /// MOSI at each rising edge of SCK.
+    sampled: Vec<bool>,
+    /// Bits returned on MISO, one per rising edge.
+    miso: Vec<bool>,
+    delays: usize,
+}
+
+#[derive(Clone)]
+struct Sck(Rc<RefCell<Bus>>);
+#[derive(Clone)]
+struct Mosi(Rc<RefCell<Bus>>);
+#[derive(Clone)]
+struct Miso(Rc<RefCell<Bus>>);
+struct Delay(Rc<RefCell<Bus>>);
+
+#[allow(deprecated)]
+impl OutputPin for Sck {
+    fn set_low(&mut self) {
+        self.0.borrow_mut().sck = false;
+    }
+
+    fn set_high(&mut self) {
+        let mut bus = self.0.borrow_mut();
+        assert!(!bus.sck, "SCK rose twice");
+        bus.sck = true;
+        let mosi = bus.mosi;
+        bus.sampled.push(mosi);
+    }
+}
+
+#[allow(deprecated)]
+impl OutputPin for Mosi {
+    fn set_low(&mut self) {
+        let mut bus = self.0.borrow_mut();
+        assert!(!bus.sck, "MOSI changed while SCK was high");
+        bus.mosi = false;
+    }
+
+    fn set_high(&mut self) {
+        let mut bus = self.0.borrow_mut();
+        assert!(!bus.sck, "MOSI changed while SCK was high");
+        bus.mosi = true;
+    }
+}
+
+#[allow(deprecated)]
+impl InputPin for Miso {
+    fn is_high(&self) -> bool {
+        self.0.borrow_mut().miso.remove(0)
+    }
+
+    fn is_low(&self) -> bool {
+        !self.is_high()
+    }
+}
+
+impl DelayUs<u16> for Delay {
+    fn delay_us(&mut self, _: u16) {
+        self.0.borrow_mut().delays += 1;
+    }
+}
+
+fn bits(word: u16, n: u8) -> Vec<bool> {
+    (0..n).rev().map(|bit| word & (1 << bit) != 0).collect()
+}
+
+fn write_only(bus: &Rc<RefCell<Bus>>) -> BitBangSpi<Sck, Mosi, NoMiso, Delay> {
+    BitBangSpi::new(Sck(bus.clone()), Mosi(bus.clone()), NoMiso, Delay(bus.clone()), 0)
+}
+
+#[test]
+fn sends_msb_first_in_mode_0() {
+    let bus = Rc::new(RefCell::new(Bus::default()));
+    bus.borrow_mut().miso = bits(0x5a, 8);
+    let mut spi = BitBangSpi::new(
+        Sck(bus.clone()),
+        Mosi(bus.clone()),
+        Miso(bus.clone()),
+        Delay(bus.clone()),
+        1,
+    );
+
+    FullDuplex::<u8>::send(&mut spi, 0xc3).unwrap();
+    let read: u8 = spi.read().unwrap();
+
+    let bus = bus.borrow();
+    assert_eq!(bus.sampled, bits(0xc3, 8));
+    assert_eq!(read, 0x5a);
+    assert!(!bus.sck);
+    assert_eq!(bus.delays, 16);
+}
+
+#[test]
+fn read_without_send_would_block() {
+    let bus = Rc::new(RefCell::new(Bus::default()));
+    let mut spi = write_only(&bus);
+
+    assert!(FullDuplex::<u8>::read(&mut spi).is_err());
+    FullDuplex::<u8>::send(&mut spi, 0xff).unwrap();
+    assert_eq!(FullDuplex::<u8>::read(&mut spi), Ok(0x00));
+    assert_eq!(bus.borrow().delays, 0);
+}
+
+#[test]
+fn nine_bit_frames() {
+    let bus = Rc::new(RefCell::new(Bus::default()));
+    let mut spi = write_only(&bus);
+
+    FullDuplex::<u16>::send(&mut spi, frame9(false, 0x22)).unwrap();
+    FullDuplex::<u16>::send(&mut spi, frame9(true, 0xc7)).unwrap();
+
+    let mut expected = bits(0x022, 9);
+    expected.extend(bits(0x1c7, 9));
+    assert_eq!(bus.borrow().sampled, expected);
+}
+
+#[test]
+#[should_panic(expected = "at most 16 bits")]
+fn more_bits_than_a_word_panics() {
+    let bus = Rc::new(RefCell::new(Bus::default()));
+    write_only(&bus).transfer_bits(0, 17);
+}
+
+#[test]
+fn screen_over_three_wire() {
+    let bus = Rc::new(RefCell::new(Bus::default()));
+    let three_wire = ThreeWire::new();
+    let (spi, dc) = three_wire.split(write_only(&bus));
+    let recorder = Recorder::new();
+
+    let mut screen = ScreenBuilder::preset(Preset::CFAP200200A0_154)
+        .new_screen(
+            spi,
+            dc,
+            recorder.cs(),
+            recorder.busy(),
+            recorder.reset(),
+            &mut recorder.delay(),
+        )
+        .unwrap();
+    screen.write_params(&Vcom { value: 0x55 }).unwrap();
+
+    let frames: Vec<u16> = bus
+        .borrow()
+        .sampled
+        .chunks(9)
+        .map(|frame| frame.iter().fold(0, |word, &bit| word << 1 | bit as u16))
+        .collect();
+    assert_eq!(
+        frames[..4],
+        [frame9(false, 0x01), frame9(true, 0xc7), frame9(true, 0x00), frame9(true, 0x00)]
+    );
+    assert_eq!(frames[frames.len() - 2..], [frame9(false, 0x2c), frame9(true, 0x55)]);
+}
@@ -0,0 +1,54 @@
+extern crate cfap_screen;
+
+use cfap_screen::*;
+
+fn a0() -> ScreenBuilder {
+    ScreenBuilder::preset(Preset::CFAP200200A0_154)
+}
+
+#[test]
+fn presets_are_valid() {
+    assert_eq!(a0().validate(), Ok(()));
+    assert_eq!(ScreenBuilder::preset(Preset::CFAP200200A1_154).validate(), Ok(()));
+}
+
+#[test]
+fn invalid_configs() {
+    let cases = vec![
+        (a0().x_size(0), ConfigError::XSize),
+        (a0().x_size(MAX_X_SIZE + 1), ConfigError::XSize),
+        (a0().y_size(0), ConfigError::YSize),
+        (a0().y_size(MAX_Y_SIZE + 1), ConfigError::YSize),
+        (a0().dummy_line(MAX_DUMMY_LINE + 1), ConfigError::DummyLine),
+        (a0().gate_line(MAX_GATE_LINE + 1), ConfigError::GateLine),
+    ];
+
+    for (builder, expected) in cases {
+        assert_eq!(builder.validate(), Err(expected));
+    }
+}
+
+#[test]
+fn limits_are_inclusive() {
+    assert!(a0().x_size(MAX_X_SIZE).y_size(MAX_Y_SIZE).validate().is_ok());
+    assert!(a0()
+        .vcom(0xff)
+        .dummy_line(MAX_DUMMY_LINE)
+        .gate_line(MAX_GATE_LINE)
+        .validate()
+        .is_ok());
+}
+
+#[test]
+fn gate_scan_bits() {
+    assert_eq!(GateScan::default(), GateScan::POR);
+    assert_eq!(GateScan::POR.bits(), 0b000);
+
+    let gate_scan = GateScan {
+        first_gate: FirstGate::G1,
+        order: GateOrder::Separated,
+        direction: ScanDirection::Reverse,
+    };
+    assert_eq!(gate_scan.bits(), 0b111);
+    assert_eq!(GateScan { order: GateOrder::Separated, ..GateScan::POR }.bits(), 0b010);
+}
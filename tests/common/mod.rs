@@ -0,0 +1,14 @@
+use cfap_screen::ImageSource;
+
+/// Streams `image` into a `Vec`, checking it yields `len` bytes.
+pub fn collect<I: ImageSource>(mut image: I) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    image
+        .stream(|b| {
+            bytes.push(b);
+            Ok::<(), ()>(())
+        })
+        .unwrap();
+    assert_eq!(bytes.len(), image.len());
+    bytes
+}
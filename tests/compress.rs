@@ -0,0 +1,89 @@
+extern crate cfap_screen;
+
+mod common;
+
+use cfap_screen::compress::*;
+use cfap_screen::emulator::Emulator;
+use cfap_screen::*;
+
+use common::collect;
+
+/// A white screen with a black box and some noise, 25 bytes per row.
+fn splash() -> Vec<u8> {
+    let mut image = vec![0xff; 5000];
+    for y in 50..150 {
+        for x in 5..20 {
+            image[y * 25 + x] = 0x00;
+        }
+    }
+    for (i, b) in image.iter_mut().enumerate().skip(4000).take(300) {
+        *b = (i * 37 % 251) as u8;
+    }
+    image
+}
+
+#[test]
+fn rle_round_trip() {
+    let image = splash();
+    let rle = encode_rle(&image);
+
+    assert!(rle.len() < image.len() / 4);
+    assert_eq!(collect(Rle::new(&rle).unwrap()), image);
+}
+
+#[test]
+fn lz_round_trip() {
+    let image = splash();
+    let lz = encode_lz(&image);
+
+    assert!(lz.len() < image.len() / 4);
+    assert_eq!(collect(Lz::new(&lz).unwrap()), image);
+}
+
+#[test]
+fn edge_cases_round_trip() {
+    let long_literal: Vec<u8> = (0..300).map(|i| (i * 7) as u8).collect();
+    let inputs = vec![
+        vec![],
+        vec![1],
+        vec![1, 1],
+        vec![2; 129],
+        vec![2; 130],
+        vec![3; 1000],
+        long_literal,
+    ];
+
+    for input in inputs {
+        assert_eq!(collect(Rle::new(&encode_rle(&input)).unwrap()), input);
+        assert_eq!(collect(Lz::new(&encode_lz(&input)).unwrap()), input);
+    }
+}
+
+#[test]
+fn known_encodings() {
+    assert_eq!(
+        collect(Rle::new(&[0x81, 0xaa, 0x01, 1, 2]).unwrap()),
+        vec![0xaa, 0xaa, 0xaa, 1, 2]
+    );
+    assert_eq!(collect(Lz::new(&[0x01, 1, 2, 0x81, 0x01]).unwrap()), vec![1, 2, 1, 2, 1, 2]);
+}
+
+#[test]
+fn malformed_data() {
+    assert_eq!(Rle::new(&[0x02, 1, 2]).unwrap_err(), CompressionError::LengthError);
+    assert_eq!(Rle::new(&[0x80]).unwrap_err(), CompressionError::LengthError);
+    assert_eq!(Lz::new(&[0x80]).unwrap_err(), CompressionError::LengthError);
+    assert_eq!(Lz::new(&[0x00, 1, 0x80, 0x01]).unwrap_err(), CompressionError::ReferenceError);
+}
+
+#[test]
+fn compressed_image_is_shown() {
+    let (emulator, mut screen) =
+        Emulator::screen(ScreenBuilder::preset(Preset::CFAP200200A0_154)).unwrap();
+    let image = splash();
+    let lz = encode_lz(&image);
+
+    screen.show_full_screen_source(Lz::new(&lz).unwrap()).unwrap();
+
+    assert_eq!(emulator.image(), image);
+}
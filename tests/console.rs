@@ -0,0 +1,134 @@
+extern crate cfap_screen;
+
+use std::fmt::Write;
+
+use cfap_screen::emulator::Emulator;
+use cfap_screen::font::{draw_text, TextStyle, FONT_5X7};
+use cfap_screen::*;
+
+/// A 200 by 200 image with `text` drawn from the top left.
+fn expected(text: &str) -> Vec<u8> {
+    let mut image = vec![0xff; 5000];
+    draw_text(&mut Canvas::new(&mut image, 200), 0, 0, text, &TextStyle::new(&FONT_5X7));
+    image
+}
+
+#[test]
+fn lines_are_shown_on_flush() {
+    let (emulator, mut screen) =
+        Emulator::screen(ScreenBuilder::preset(Preset::CFAP200200A0_154)).unwrap();
+    let mut buf = vec![0x00; 5000];
+    let mut console = Console::new(&mut buf, 200, 200, TextStyle::new(&FONT_5X7)).unwrap();
+    assert_eq!((console.cols(), console.rows()), (33, 25));
+
+    writeln!(console, "boot {}", 1).unwrap();
+    assert_eq!(console.flush(&mut screen).unwrap(), Some(Refresh::Full));
+    assert_eq!(emulator.image(), expected("boot 1"));
+
+    write!(console, "ok").unwrap();
+    assert_eq!(console.cursor(), (2, 1));
+    assert_eq!(console.flush(&mut screen).unwrap(), Some(Refresh::Partial));
+    assert_eq!(console.flush(&mut screen).unwrap(), None);
+    assert_eq!(emulator.image(), expected("boot 1\nok"));
+    assert!(emulator.errors().is_empty(), "{:?}", emulator.errors());
+}
+
+#[test]
+fn long_lines_wrap() {
+    let mut buf = vec![0x00; 5000];
+    let mut console = Console::new(&mut buf, 200, 200, TextStyle::new(&FONT_5X7)).unwrap();
+
+    let line = "x".repeat(40);
+    write!(console, "{}", line).unwrap();
+
+    assert_eq!(console.cursor(), (7, 1));
+    let wrapped = format!("{}\n{}", &line[..33], &line[33..]);
+    assert_eq!(console.as_bytes(), &expected(&wrapped)[..]);
+}
+
+#[test]
+fn full_screens_scroll() {
+    let (emulator, mut screen) =
+        Emulator::screen(ScreenBuilder::preset(Preset::CFAP200200A0_154)).unwrap();
+    let mut buf = vec![0x00; 5000];
+    let mut console = Console::new(&mut buf, 200, 200, TextStyle::new(&FONT_5X7)).unwrap();
+    console.flush(&mut screen).unwrap();
+
+    let mut text = String::new();
+    for i in 0..25 {
+        writeln!(console, "line {}", i).unwrap();
+        writeln!(text, "line {}", i).unwrap();
+    }
+    // the last newline only scrolls once there is more to show
+    assert_eq!(console.cursor(), (0, 25));
+    assert_eq!(console.as_bytes(), &expected(&text)[..]);
+
+    write!(console, "line 25").unwrap();
+    assert_eq!(console.cursor(), (7, 24));
+    let text = text.split_off(text.find("line 1\n").unwrap()) + "line 25";
+    assert_eq!(console.as_bytes(), &expected(&text)[..]);
+
+    assert_eq!(console.flush(&mut screen).unwrap(), Some(Refresh::Partial));
+    assert_eq!(emulator.image(), expected(&text));
+    assert!(emulator.errors().is_empty(), "{:?}", emulator.errors());
+}
+
+#[test]
+fn newlines_at_the_bottom_scroll_a_line_each() {
+    let mut buf = vec![0x00; 5000];
+    let mut console = Console::new(&mut buf, 200, 200, TextStyle::new(&FONT_5X7)).unwrap();
+
+    let mut text = String::new();
+    for i in 0..25 {
+        writeln!(text, "line {}", i).unwrap();
+    }
+    text.push_str("\n\nend");
+    write!(console, "{}", text).unwrap();
+
+    // 28 lines, so the first 3 have scrolled off above the two blank lines
+    assert_eq!(console.cursor(), (3, 24));
+    let text = text.split_off(text.find("line 3\n").unwrap());
+    assert_eq!(console.as_bytes(), &expected(&text)[..]);
+
+    // the line stays at most `rows()` however many newlines are written
+    for _ in 0..100 {
+        console.write_char('\n').unwrap();
+        assert_eq!(console.cursor(), (0, 25));
+    }
+    assert_eq!(console.as_bytes(), &expected("")[..]);
+}
+
+#[test]
+fn full_refreshes_follow_the_policy() {
+    let (emulator, mut screen) =
+        Emulator::screen(ScreenBuilder::preset(Preset::CFAP200200A0_154)).unwrap();
+    let mut buf = vec![0x00; 5000];
+    let mut console = Console::new(&mut buf, 200, 200, TextStyle::new(&FONT_5X7)).unwrap();
+    console.tracker_mut().policy = RefreshPolicy {
+        max_partial_updates: Some(2),
+        ..RefreshPolicy::NEVER_FULL
+    };
+
+    let mut refreshes = Vec::new();
+    for i in 0..5 {
+        writeln!(console, "{}", i).unwrap();
+        refreshes.push(console.flush(&mut screen).unwrap().unwrap());
+    }
+    assert_eq!(
+        refreshes,
+        [Refresh::Full, Refresh::Partial, Refresh::Partial, Refresh::Full, Refresh::Partial]
+    );
+
+    console.clear();
+    assert_eq!(console.cursor(), (0, 0));
+    assert_eq!(console.flush(&mut screen).unwrap(), Some(Refresh::Full));
+    assert_eq!(emulator.image(), vec![0xff; 5000]);
+}
+
+#[test]
+fn console_checks_its_buffer() {
+    let mut buf = vec![0x00; 4999];
+    assert!(Console::new(&mut buf, 200, 200, TextStyle::new(&FONT_5X7)).is_none());
+    let mut buf = vec![0x00; 4];
+    assert!(Console::new(&mut buf, 4, 8, TextStyle::new(&FONT_5X7)).is_none());
+}
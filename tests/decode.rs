@@ -0,0 +1,155 @@
+extern crate cfap_screen;
+
+use cfap_screen::decode::{decode, Decoder, Event};
+use cfap_screen::mock::Recorder;
+use cfap_screen::params::*;
+use cfap_screen::*;
+
+#[test]
+fn decodes_init_and_full_update() {
+    let (recorder, mut screen) =
+        Recorder::screen(ScreenBuilder::preset(Preset::CFAP200200A0_154)).unwrap();
+    screen.show_full_screen_image(&[0; 5000]).unwrap();
+
+    let events: Vec<Event> = decode(recorder.bytes()).collect();
+
+    assert_eq!(
+        events,
+        vec![
+            Event::DriverOutputControl(DriverOutput { gates: 200, gate_scan: GateScan::POR }),
+            Event::BoosterSoftStartControl(SoftStart { phase1: 0xd7, phase2: 0xd6, phase3: 0x9d }),
+            Event::WriteVcomRegister(Vcom { value: 0xa8 }),
+            Event::SetDummyLinePeriod(DummyLinePeriod { period: 0x1a }),
+            Event::SetGateLineWidth(GateLineWidth { width: 0x08 }),
+            Event::DataEntryModeSetting(DataEntryMode {
+                id: EntryMode::XIncrementYDecrement,
+                am: AddressDirection::X,
+            }),
+            Event::WriteLutRegister(Lut(LUT_FULL_CFAP200200A0_154)),
+            Event::DisplayUpdateControl2(UpdateSequence::POWER_ON),
+            Event::MasterActivation,
+            Event::SetRamXAddressStartEndPosition(RamXWindow { start: 0, end: 24 }),
+            Event::SetRamYAddressStartEndPosition(RamYWindow { start: 199, end: 0 }),
+            Event::SetRamXAddressCounter(RamXCounter { x: 0 }),
+            Event::SetRamYAddressCounter(RamYCounter { y: 199 }),
+            Event::WriteRam { len: 5000 },
+            Event::DisplayUpdateControl2(UpdateSequence::FULL),
+            Event::MasterActivation,
+            Event::Nop,
+            Event::DisplayUpdateControl2(UpdateSequence::POWER_OFF),
+            Event::MasterActivation,
+        ]
+    );
+}
+
+#[test]
+fn events_are_returned_when_the_next_command_starts() {
+    let mut decoder = Decoder::new();
+
+    assert_eq!(decoder.push(false, 0x4e), None);
+    assert_eq!(decoder.push(true, 0x03), None);
+    assert_eq!(decoder.push(false, 0x20), Some(Event::SetRamXAddressCounter(RamXCounter { x: 3 })));
+    assert_eq!(decoder.finish(), Some(Event::MasterActivation));
+    assert_eq!(decoder.finish(), None);
+}
+
+#[test]
+fn unexpected_streams() {
+    let bytes = vec![
+        (true, 0x01),
+        (true, 0x02),
+        (false, 0x4e),
+        (false, 0x26),
+        (true, 0x00),
+        (true, 0x00),
+        (true, 0x00),
+    ];
+
+    let events: Vec<Event> = decode(bytes).collect();
+
+    assert_eq!(
+        events,
+        vec![
+            Event::OrphanData { len: 2 },
+            Event::Malformed { opcode: 0x4e, len: 0, error: ParamError::LengthError },
+            Event::Unknown { opcode: 0x26, len: 3 },
+        ]
+    );
+}
+
+#[test]
+fn negative_temperature() {
+    let bytes = vec![(false, 0x1a), (true, 0xe7), (true, 0x00)];
+
+    let events: Vec<Event> = decode(bytes).collect();
+
+    assert_eq!(events, vec![Event::TemperatureSensorControl(Temperature { sixteenths: -400 })]);
+}
+
+#[test]
+fn reserved_bits_are_malformed() {
+    let bytes = vec![
+        (false, 0x11),
+        (true, 0x0b),
+        (false, 0x21),
+        (true, 0x8f),
+        (false, 0x45),
+        (true, 0xc7),
+        (true, 0x02),
+        (true, 0x00),
+        (true, 0x00),
+    ];
+
+    let events: Vec<Event> = decode(bytes).collect();
+
+    assert_eq!(
+        events,
+        vec![
+            Event::Malformed { opcode: 0x11, len: 1, error: ParamError::ReservedError },
+            Event::DisplayUpdateControl1(UpdateOptions {
+                bypass: true,
+                bypass_value: false,
+                c: 0b11,
+                gs: 0b11,
+            }),
+            Event::Malformed { opcode: 0x45, len: 4, error: ParamError::ReservedError },
+        ]
+    );
+}
+
+#[test]
+fn display() {
+    let lines = [
+        (
+            Event::DriverOutputControl(DriverOutput {
+                gates: 200,
+                gate_scan: GateScan {
+                    first_gate: FirstGate::G1,
+                    order: GateOrder::Interlaced,
+                    direction: ScanDirection::Reverse,
+                },
+            }),
+            "DriverOutputControl gates=200 gd=1 sm=0 tb=1",
+        ),
+        (
+            Event::SetRamYAddressStartEndPosition(RamYWindow { start: 199, end: 0 }),
+            "SetRamYAddressStartEndPosition start=199 end=0",
+        ),
+        (
+            Event::DataEntryModeSetting(DataEntryMode {
+                id: EntryMode::XIncrementYDecrement,
+                am: AddressDirection::Y,
+            }),
+            "DataEntryModeSetting id=0b01 am=1",
+        ),
+        (Event::Unknown { opcode: 0x2f, len: 1 }, "Unknown opcode=0x2f len=1"),
+        (
+            Event::Malformed { opcode: 0x4e, len: 2, error: ParamError::ReservedError },
+            "Malformed opcode=0x4e len=2 error=ReservedError",
+        ),
+    ];
+
+    for &(event, line) in lines.iter() {
+        assert_eq!(event.to_string(), line);
+    }
+}
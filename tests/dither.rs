@@ -0,0 +1,105 @@
+extern crate cfap_screen;
+
+use cfap_screen::dither::*;
+use cfap_screen::emulator::Emulator;
+use cfap_screen::*;
+
+const METHODS: [Dither; 4] = [
+    Dither::Threshold(128),
+    Dither::Bayer,
+    Dither::FloydSteinberg,
+    Dither::Atkinson,
+];
+
+fn white_pixels(image: &[u8]) -> u32 {
+    image.iter().map(|b| b.count_ones()).sum()
+}
+
+#[test]
+fn black_and_white_are_kept() {
+    let mut scratch = [0; scratch_len(16)];
+    for &method in METHODS.iter() {
+        let mut out = [0; 2 * 16];
+
+        dither(method, 16, &[0; 16 * 16], &mut out, &mut scratch).unwrap();
+        assert_eq!(out, [0; 32], "{:?}", method);
+
+        dither(method, 16, &[255; 16 * 16], &mut out, &mut scratch).unwrap();
+        assert_eq!(out, [0xff; 32], "{:?}", method);
+    }
+}
+
+#[test]
+fn mid_gray_is_about_half_white() {
+    let mut scratch = [0; scratch_len(64)];
+    for &method in [Dither::Bayer, Dither::FloydSteinberg, Dither::Atkinson].iter() {
+        let mut out = [0; 8 * 64];
+
+        dither(method, 64, &[128; 64 * 64], &mut out, &mut scratch).unwrap();
+
+        let white = white_pixels(&out);
+        assert!(white > 1900 && white < 2200, "{:?}: {}", method, white);
+    }
+}
+
+#[test]
+fn bayer_pattern() {
+    let mut ditherer = Ditherer::new(Dither::Bayer, 8, &mut []).unwrap();
+    let mut out = [0; 1];
+
+    ditherer.row(&[64; 8], &mut out).unwrap();
+    assert_eq!(out, [0b1010_1010]);
+    ditherer.row(&[64; 8], &mut out).unwrap();
+    assert_eq!(out, [0b0000_0000]);
+}
+
+#[test]
+fn unused_bits_are_set() {
+    let mut ditherer = Ditherer::new(Dither::Threshold(128), 10, &mut []).unwrap();
+    let mut out = [0; 2];
+
+    ditherer.row(&[0; 10], &mut out).unwrap();
+
+    assert_eq!(out, [0x00, 0x3f]);
+}
+
+#[test]
+fn bad_lengths() {
+    let mut scratch = [0; scratch_len(8) - 1];
+    assert_eq!(
+        Ditherer::new(Dither::Atkinson, 8, &mut scratch).err(),
+        Some(DitherError::ScratchError)
+    );
+
+    let mut ditherer = Ditherer::new(Dither::Bayer, 8, &mut []).unwrap();
+    assert_eq!(ditherer.row(&[0; 7], &mut [0; 1]), Err(DitherError::LengthError));
+    assert_eq!(ditherer.row(&[0; 8], &mut [0; 2]), Err(DitherError::LengthError));
+    assert_eq!(
+        dither(Dither::Bayer, 8, &[0; 12], &mut [0; 2], &mut []),
+        Err(DitherError::LengthError)
+    );
+}
+
+#[test]
+fn dithered_rows_are_streamed() {
+    let (emulator, mut screen) =
+        Emulator::screen(ScreenBuilder::preset(Preset::CFAP200200A0_154)).unwrap();
+    let mut scratch = [0; scratch_len(200)];
+    let mut gray = [0; 200];
+    let mut out = [0; 25];
+
+    let ditherer = Ditherer::new(Dither::FloydSteinberg, 200, &mut scratch).unwrap();
+    let source = Dithered::new(ditherer, &mut gray, &mut out, 200, |_, row: &mut [u8]| {
+        // left half black, right half white
+        for (x, g) in row.iter_mut().enumerate() {
+            *g = if x < 100 { 0 } else { 255 };
+        }
+    })
+    .unwrap();
+    screen.show_full_screen_source(source).unwrap();
+
+    assert!(!emulator.pixel(0, 0));
+    assert!(!emulator.pixel(99, 199));
+    assert!(emulator.pixel(100, 0));
+    assert!(emulator.pixel(199, 199));
+}
@@ -0,0 +1,172 @@
+extern crate cfap_screen;
+
+use cfap_screen::emulator::Emulator;
+use cfap_screen::*;
+
+fn a0() -> ScreenBuilder {
+    ScreenBuilder::preset(Preset::CFAP200200A0_154)
+}
+
+fn test_image() -> Vec<u8> {
+    (0..5000u32).map(|i| (i * 7 + i / 25) as u8).collect()
+}
+
+#[test]
+fn full_screen_image_is_shown() {
+    let (emulator, mut screen) = Emulator::screen(a0()).unwrap();
+    let image = test_image();
+
+    screen.show_full_screen_image(&image).unwrap();
+
+    assert_eq!(emulator.image(), image);
+    assert_eq!(emulator.updates(), 1);
+    assert!(emulator.errors().is_empty(), "{:?}", emulator.errors());
+}
+
+#[test]
+fn reverse_scan_shows_the_same_image() {
+    let gate_scan = GateScan {
+        direction: ScanDirection::Reverse,
+        ..GateScan::POR
+    };
+    let (emulator, mut screen) = Emulator::screen(a0().gate_scan(gate_scan)).unwrap();
+    let image = test_image();
+
+    screen.show_full_screen_image(&image).unwrap();
+
+    assert_eq!(emulator.image(), image);
+    assert!(emulator.errors().is_empty(), "{:?}", emulator.errors());
+}
+
+#[test]
+fn partial_image_is_shown_after_update() {
+    let (emulator, mut screen) = Emulator::screen(a0()).unwrap();
+
+    // rows 10 to 11 from the top are RAM rows 189 and 188
+    screen.load_partial_image(2, 2, 188, 2, &[0x00, 0x0f, 0xf0, 0x55]).unwrap();
+    assert_eq!(emulator.ram_byte(2, 188), 0x00);
+    assert_eq!(emulator.ram_byte(3, 189), 0x55);
+    assert!(emulator.pixel(16, 11));
+
+    screen.load_partial_update_lut().unwrap();
+    screen.power_on().unwrap();
+    screen.update_partial().unwrap();
+
+    let image = emulator.image();
+    assert_eq!(image[11 * 25 + 2..11 * 25 + 4], [0x00, 0x0f]);
+    assert_eq!(image[10 * 25 + 2..10 * 25 + 4], [0xf0, 0x55]);
+    assert!(!emulator.pixel(16, 11));
+    assert!(emulator.errors().is_empty(), "{:?}", emulator.errors());
+}
+
+#[test]
+fn update_without_power_is_an_error() {
+    let (emulator, mut screen) = Emulator::screen(a0()).unwrap();
+
+    screen.update_partial().unwrap();
+
+    assert_eq!(emulator.updates(), 0);
+    assert_eq!(emulator.errors().len(), 1);
+}
+
+#[test]
+fn address_counter_follows_am() {
+    let (emulator, mut screen) = Emulator::screen(a0()).unwrap();
+    emulator.set_gate_zero_at_top(true);
+
+    // Y increment, X increment, Y direction first
+    screen.write_cmd_string(Command::DataEntryModeSetting, &[0b111]).unwrap();
+    screen.set_display_area(4, 5, 20, 21).unwrap();
+    screen.load_image(&[1, 2, 3, 4]).unwrap();
+
+    assert_eq!(emulator.ram_byte(4, 20), 1);
+    assert_eq!(emulator.ram_byte(4, 21), 2);
+    assert_eq!(emulator.ram_byte(5, 20), 3);
+    assert_eq!(emulator.ram_byte(5, 21), 4);
+}
+
+#[test]
+fn reserved_bits_are_an_error() {
+    let (emulator, mut screen) = Emulator::screen(a0()).unwrap();
+    screen.set_display_area(4, 5, 20, 21).unwrap();
+
+    // a window end of 0x25 sets a reserved bit, leaving the window as it was
+    screen.write_cmd_string(Command::SetRamXAddressStartEndPosition, &[0x04, 0x25]).unwrap();
+    screen.write_cmd_string(Command::SetRamXAddressCounter, &[4]).unwrap();
+    screen.load_image(&[1, 2, 3]).unwrap();
+
+    assert_eq!(emulator.errors().len(), 1);
+    assert_eq!(emulator.ram_byte(4, 20), 1);
+    assert_eq!(emulator.ram_byte(5, 20), 2);
+    assert_eq!(emulator.ram_byte(4, 21), 3);
+}
+
+#[test]
+fn busy_is_held_after_activation() {
+    let (emulator, mut screen) = Emulator::screen(a0()).unwrap();
+    emulator.set_busy_polls(3);
+
+    screen.write_cmd_string(Command::DisplayUpdateControl2, &[0xc0]).unwrap();
+    screen.write_cmd(Command::MasterActivation).unwrap();
+    assert!(emulator.is_busy());
+
+    screen.write_cmd(Command::Nop).unwrap();
+    assert_eq!(emulator.errors().len(), 1);
+
+    screen.power_off().unwrap();
+    assert!(!emulator.is_busy());
+}
+
+#[test]
+fn pbm_export() {
+    let (emulator, mut screen) = Emulator::screen(a0()).unwrap();
+    let image = test_image();
+    screen.show_full_screen_image(&image).unwrap();
+
+    let mut pbm = Vec::new();
+    emulator.write_pbm(&mut pbm).unwrap();
+
+    let header = b"P4\n200 200\n";
+    assert_eq!(pbm[..header.len()], header[..]);
+    let inverted: Vec<u8> = image.iter().map(|b| !b).collect();
+    assert_eq!(pbm[header.len()..], inverted[..]);
+}
+
+#[test]
+fn png_export() {
+    let emulator = Emulator::new(13, 5);
+
+    let mut png = Vec::new();
+    emulator.write_png(&mut png).unwrap();
+
+    assert_eq!(png[..8], b"\x89PNG\r\n\x1a\n"[..]);
+    assert_eq!(png[12..16], b"IHDR"[..]);
+    assert_eq!(png[16..24], [0, 0, 0, 13, 0, 0, 0, 5]);
+    assert_eq!(png[png.len() - 8..png.len() - 4], b"IEND"[..]);
+}
+
+#[test]
+fn unaligned_width_is_padded() {
+    let emulator = Emulator::new(13, 5);
+
+    assert_eq!(emulator.image(), vec![0xff; 10]);
+}
+
+#[test]
+fn odd_height_with_first_gate_swapped_and_reverse_scan() {
+    let gate_scan = GateScan {
+        first_gate: FirstGate::G1,
+        order: GateOrder::Interlaced,
+        direction: ScanDirection::Reverse,
+    };
+    let (emulator, mut screen) = Emulator::screen(a0().y_size(199).gate_scan(gate_scan)).unwrap();
+    let image: Vec<u8> = (0..199 * 25u32).map(|i| (i / 25) as u8).collect();
+
+    screen.show_full_screen_image(&image).unwrap();
+
+    // rows swap in pairs, and the last has no gate to swap onto
+    let rows: Vec<u8> = emulator.image().chunks(25).map(|row| row[0]).collect();
+    let expected: Vec<u8> = (0..198).map(|y| y ^ 1).chain(Some(0xff)).collect();
+    assert_eq!(rows, expected);
+    assert!(emulator.errors().is_empty(), "{:?}", emulator.errors());
+}
@@ -0,0 +1,135 @@
+extern crate cfap_screen;
+
+use cfap_screen::font::{draw_text, draw_text_in, Align, TextStyle, FONT_5X7};
+use cfap_screen::*;
+
+/// The canvas as rows of `#` for black and `.` for white.
+fn dump(canvas: &Canvas) -> Vec<String> {
+    (0..canvas.rows())
+        .map(|y| {
+            (0..canvas.width())
+                .map(|x| match canvas.pixel(x, canvas.top() + y) {
+                    Some(Color::Black) => '#',
+                    _ => '.',
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn black_columns(canvas: &Canvas) -> Vec<u16> {
+    (0..canvas.width())
+        .filter(|&x| (canvas.top()..canvas.top() + canvas.rows())
+            .any(|y| canvas.pixel(x, y) == Some(Color::Black)))
+        .collect()
+}
+
+#[test]
+fn glyphs_are_drawn_from_the_table() {
+    let mut buf = [0xff; 8];
+    let mut canvas = Canvas::new(&mut buf, 8);
+
+    draw_text(&mut canvas, 1, 0, "A", &TextStyle::new(&FONT_5X7));
+
+    assert_eq!(
+        dump(&canvas),
+        [
+            "..###...", ".#...#..", ".#...#..", ".#...#..",
+            ".#####..", ".#...#..", ".#...#..", "........",
+        ]
+    );
+    assert_eq!(FONT_5X7.glyph('\u{e9}'), FONT_5X7.glyph('?'));
+}
+
+#[test]
+fn scaled_text() {
+    let mut buf = [0xff; 2 * 14];
+    let mut canvas = Canvas::new(&mut buf, 16);
+    let style = TextStyle { scale: 2, ..TextStyle::new(&FONT_5X7) };
+
+    draw_text(&mut canvas, 0, 0, "!", &style);
+
+    // the 1 pixel wide stroke of `!` is two pixels wide and tall
+    assert_eq!(black_columns(&canvas), [4, 5]);
+    assert_eq!(canvas.pixel(4, 11), Some(Color::White));
+    assert_eq!(canvas.pixel(4, 12), Some(Color::Black));
+    assert_eq!(canvas.pixel(5, 13), Some(Color::Black));
+    assert_eq!(style.advance(), 12);
+    assert_eq!(style.line_height(), 16);
+}
+
+#[test]
+fn alignment() {
+    let style = TextStyle::new(&FONT_5X7);
+    assert_eq!(style.width_of(3), 17);
+
+    for &(align, x, first, last) in [
+        (Align::Left, 10, 10, 26),
+        (Align::Center, 50, 42, 58),
+        (Align::Right, 99, 82, 98),
+    ].iter() {
+        let mut buf = [0xff; 13 * 8];
+        let mut canvas = Canvas::new(&mut buf, 100);
+        draw_text(&mut canvas, x, 0, "HHH", &TextStyle { align, ..style });
+
+        let columns = black_columns(&canvas);
+        assert_eq!((columns[0], *columns.last().unwrap()), (first, last), "{:?}", align);
+    }
+}
+
+#[test]
+fn text_is_clipped() {
+    let style = TextStyle { background: Some(Color::White), ..TextStyle::new(&FONT_5X7) };
+
+    // off the left edge, only the last column of `H` is drawn
+    let mut buf = [0x00; 8];
+    let mut canvas = Canvas::new(&mut buf, 8);
+    draw_text(&mut canvas, -4, 0, "H", &style);
+    assert_eq!(black_columns(&canvas), [0, 1, 2, 3, 4, 5, 6, 7]);
+    assert_eq!(canvas.pixel(0, 0), Some(Color::Black));
+    assert_eq!(canvas.pixel(1, 0), Some(Color::Black));
+    assert_eq!(canvas.pixel(0, 1), Some(Color::Black));
+
+    // a band only holds some of the rows
+    let mut buf = [0xff; 2];
+    let mut canvas = Canvas::new_band(&mut buf, 8, 8, 3);
+    draw_text(&mut canvas, 0, 0, "-", &TextStyle::new(&FONT_5X7));
+    assert_eq!(dump(&canvas), ["#####...", "........"]);
+}
+
+#[test]
+fn text_wraps_at_spaces() {
+    // 4 characters to a line, 3 lines
+    let area = Window { x: 0, y: 0, w: 24, h: 24 };
+    let mut buf = [0xff; 3 * 24];
+    let mut canvas = Canvas::new(&mut buf, 24);
+    let style = TextStyle::new(&FONT_5X7);
+    assert_eq!(style.fit(area.w), 4);
+
+    let text = "ab cd\nabcdefgh ijk";
+    let drawn = draw_text_in(&mut canvas, &area, text, &style);
+    assert_eq!(drawn, text.find("efgh").unwrap());
+
+    // "ab", "cd", then "abcd" split from the long word
+    let mut expected = [0xff; 3 * 24];
+    draw_text(&mut Canvas::new(&mut expected, 24), 0, 0, "ab\ncd\nabcd", &style);
+    assert_eq!(canvas.as_bytes(), &expected[..]);
+
+    let drawn = draw_text_in(&mut canvas, &area, "one two", &style);
+    assert_eq!(drawn, 7);
+}
+
+#[test]
+fn wrapped_text_is_aligned_and_clipped_to_its_area() {
+    let area = Window { x: 8, y: 8, w: 16, h: 8 };
+    let mut buf = [0xff; 4 * 32];
+    let mut canvas = Canvas::new(&mut buf, 32);
+    let style = TextStyle { align: Align::Right, ..TextStyle::new(&FONT_5X7) };
+
+    let drawn = draw_text_in(&mut canvas, &area, "I\nI", &style);
+    assert_eq!(drawn, 2);
+
+    // `I` is 3 columns wide in its 5 column cell
+    assert_eq!(black_columns(&canvas), [20, 21, 22]);
+    assert!((0..32).all(|x| (0..8).chain(16..32).all(|y| canvas.pixel(x, y) == Some(Color::White))));
+}
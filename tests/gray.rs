@@ -0,0 +1,110 @@
+extern crate cfap_screen;
+
+mod common;
+
+use cfap_screen::emulator::Emulator;
+use cfap_screen::mock::Recorder;
+use cfap_screen::*;
+
+use common::collect;
+
+/// Four columns of about 50 pixels, black to white, packed 2 bits per pixel.
+/// The edges are at x = 52, 100, and 152.
+fn columns() -> Vec<u8> {
+    let row: Vec<u8> = (0..50).map(|x| [0x00, 0x55, 0xaa, 0xff][x * 4 / 50]).collect();
+    row.iter().cycle().take(50 * 200).cloned().collect()
+}
+
+/// Dark and light gray LUTs for the tests, not tuned for any panel.
+fn luts() -> [[u8; LUT_LEN]; 2] {
+    [gray_lut(10).unwrap(), gray_lut(4).unwrap()]
+}
+
+#[test]
+fn planes() {
+    // levels 0 1 2 3 3 2 1 0, then 2 in an unaligned last pixel
+    let image = [0b00_01_10_11, 0b11_10_01_00, 0b10_00_00_00];
+
+    assert_eq!(gray_image_len(9, 1), 3);
+    assert_eq!(collect(GrayPlane::level(&image, 9, 0)), vec![0b0111_1110, 0xff]);
+    assert_eq!(collect(GrayPlane::level(&image, 9, 1)), vec![0b1011_1101, 0xff]);
+    assert_eq!(collect(GrayPlane::level(&image, 9, 2)), vec![0b1101_1011, 0x7f]);
+    assert_eq!(collect(GrayPlane::level(&image, 9, 3)), vec![0b1110_0111, 0xff]);
+}
+
+#[test]
+fn gray_lut_drives_only_new_black_pixels_to_vsl() {
+    let waveform = Waveform::decode(&gray_lut(10).unwrap()).unwrap();
+
+    let phase = waveform.phases[0];
+    assert_eq!(phase.from_0_to_0, Voltage::Vsl);
+    assert_eq!(phase.from_1_to_0, Voltage::Vsl);
+    assert_eq!(phase.from_0_to_1, Voltage::Vss);
+    assert_eq!(phase.from_1_to_1, Voltage::Vss);
+    assert_eq!(phase.frames, 10);
+    assert!(waveform.phases[1..].iter().all(|p| p.frames == 0));
+
+    assert_eq!(gray_lut(MAX_FRAMES + 1), Err(WaveformError::FramesOutOfRange(0)));
+}
+
+#[test]
+fn gray_image_is_shown_one_level_at_a_time() {
+    let (recorder, mut screen) =
+        Recorder::screen(ScreenBuilder::preset(Preset::CFAP200200A0_154)).unwrap();
+    recorder.clear();
+    let image = columns();
+
+    screen.show_gray_image(&image, &luts()).unwrap();
+
+    let commands = recorder.commands();
+    let luts: Vec<&Vec<u8>> = commands.iter().filter(|c| c.0 == 0x32).map(|c| &c.1).collect();
+    assert_eq!(luts.len(), 3);
+    assert_eq!(luts[0][..], LUT_FULL_CFAP200200A0_154[..]);
+    assert_eq!(luts[1][..], gray_lut(10).unwrap()[..]);
+    assert_eq!(luts[2][..], gray_lut(4).unwrap()[..]);
+
+    let planes: Vec<&Vec<u8>> = commands.iter().filter(|c| c.0 == 0x24).map(|c| &c.1).collect();
+    assert_eq!(planes.len(), 3);
+    let mut black = vec![0x00; 6];
+    black.push(0x0f);
+    black.extend(vec![0xff; 18]);
+    assert_eq!(planes[0][..25], black[..]);
+    let mut dark = vec![0xff; 6];
+    dark.push(0xf0);
+    dark.extend(vec![0x00; 5]);
+    dark.push(0x0f);
+    dark.extend(vec![0xff; 12]);
+    assert_eq!(planes[1][..25], dark[..]);
+    let mut light = vec![0xff; 12];
+    light.push(0xf0);
+    light.extend(vec![0x00; 6]);
+    light.extend(vec![0xff; 6]);
+    assert_eq!(planes[2][..25], light[..]);
+
+    let sequences: Vec<u8> = commands.iter().filter(|c| c.0 == 0x22).map(|c| c.1[0]).collect();
+    assert_eq!(sequences, vec![0xc0, 0xc7, 0xc0, 0x04, 0xc0, 0x04, 0xc3]);
+}
+
+#[test]
+fn gray_image_on_emulator() {
+    let (emulator, mut screen) =
+        Emulator::screen(ScreenBuilder::preset(Preset::CFAP200200A1_154)).unwrap();
+
+    screen.show_gray_image(&columns(), &luts()).unwrap();
+
+    assert_eq!(emulator.updates(), 3);
+    assert!(emulator.errors().is_empty(), "{:?}", emulator.errors());
+}
+
+#[test]
+fn gray_image_checks_length() {
+    let (recorder, mut screen) =
+        Recorder::screen(ScreenBuilder::preset(Preset::CFAP200200A0_154)).unwrap();
+    recorder.clear();
+
+    match screen.show_gray_image(&[0; 5000], &luts()) {
+        Err(ScreenError::LengthError) => {}
+        _ => panic!("expected a length error"),
+    }
+    assert!(recorder.events().is_empty());
+}
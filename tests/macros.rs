@@ -0,0 +1,38 @@
+extern crate cfap_screen;
+
+use cfap_screen::include_panel_image;
+
+const MARK: [u8; 6] = [0x7f, 0xbf, 0xbf, 0x7f, 0xc0, 0xff];
+
+#[test]
+fn every_format_packs_the_same() {
+    assert_eq!(include_panel_image!("tests/images/mark.pbm"), MARK);
+    assert_eq!(include_panel_image!("tests/images/mark_raw.pbm"), MARK);
+    assert_eq!(include_panel_image!("tests/images/mark.bmp"), MARK);
+    assert_eq!(include_panel_image!("tests/images/mark_1bit.bmp"), MARK);
+}
+
+#[test]
+fn rotation() {
+    assert_eq!(
+        include_panel_image!("tests/images/mark.pbm", rotation = 90),
+        [0xdf, 0xbf, 0x7f, 0x7f, 0x7f, 0x7f, 0x7f, 0x7f, 0xbf, 0xdf]
+    );
+    assert_eq!(
+        include_panel_image!("tests/images/mark.pbm", rotation = 180),
+        [0xc0, 0xff, 0xbf, 0x7f, 0x7f, 0xbf]
+    );
+}
+
+#[test]
+fn padded_to_panel_size() {
+    static PADDED: [u8; 8] = include_panel_image!("tests/images/mark.pbm", width = 16, height = 4);
+
+    assert_eq!(PADDED, [0x7f, 0xbf, 0xbf, 0x7f, 0xc0, 0xff, 0xff, 0xff]);
+}
+
+#[test]
+fn threshold() {
+    assert_eq!(include_panel_image!("tests/images/ramp.pgm"), [0x00, 0xff]);
+    assert_eq!(include_panel_image!("tests/images/ramp.pgm", threshold = 200), [0x00, 0x0f]);
+}
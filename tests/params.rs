@@ -0,0 +1,79 @@
+extern crate cfap_screen;
+
+use cfap_screen::mock::Recorder;
+use cfap_screen::params::*;
+use cfap_screen::*;
+
+fn round_trip<P: Parameters + PartialEq + core::fmt::Debug>(params: P, bytes: &[u8]) {
+    assert_eq!(params.encode().as_ref(), bytes);
+    assert_eq!(P::decode(bytes), Ok(params));
+}
+
+#[test]
+fn encodes_documented_bytes() {
+    round_trip(
+        DriverOutput {
+            gates: 300,
+            gate_scan: GateScan {
+                first_gate: FirstGate::G1,
+                order: GateOrder::Interlaced,
+                direction: ScanDirection::Reverse,
+            },
+        },
+        &[0x2b, 0x01, 0b101],
+    );
+    round_trip(
+        DataEntryMode {
+            id: EntryMode::XIncrementYDecrement,
+            am: AddressDirection::Y,
+        },
+        &[0b101],
+    );
+    round_trip(RamYWindow { start: 299, end: 0 }, &[0x2b, 0x01, 0x00, 0x00]);
+    round_trip(RamXCounter { x: 24 }, &[24]);
+    round_trip(Temperature::from_celsius(-25), &[0xe7, 0x00]);
+    round_trip(Temperature { sixteenths: 0x191 }, &[0x19, 0x10]);
+    round_trip(UpdateSequence::CLOCK_ENABLE | UpdateSequence::ANALOG_ENABLE, &[0xc0]);
+    round_trip(
+        UpdateOptions {
+            bypass: true,
+            bypass_value: false,
+            c: 0,
+            gs: 0b01,
+        },
+        &[0x81],
+    );
+}
+
+#[test]
+fn update_sequence_flags() {
+    let mut sequence = UpdateSequence::POWER_ON;
+    sequence |= UpdateSequence::DISPLAY_PATTERN;
+    sequence |= UpdateSequence::ANALOG_DISABLE | UpdateSequence::CLOCK_DISABLE;
+
+    assert_eq!(sequence, UpdateSequence::FULL);
+    assert!(sequence.contains(UpdateSequence::PARTIAL));
+    assert!(!UpdateSequence::POWER_ON.contains(UpdateSequence::DISPLAY_PATTERN));
+}
+
+#[test]
+fn decode_rejects_bad_data() {
+    assert_eq!(RamYWindow::decode(&[0, 0, 0]), Err(ParamError::LengthError));
+    assert_eq!(RamYCounter::decode(&[0, 0x02]), Err(ParamError::ReservedError));
+    assert_eq!(DataEntryMode::decode(&[0b1000]), Err(ParamError::ReservedError));
+    assert_eq!(DeepSleep::decode(&[0x02]), Err(ParamError::ReservedError));
+}
+
+#[test]
+fn write_params_sends_command_and_data() {
+    let (recorder, mut screen) =
+        Recorder::screen(ScreenBuilder::preset(Preset::CFAP200200A0_154)).unwrap();
+    recorder.clear();
+
+    screen.write_params(&RamXWindow { start: 2, end: 5 }).unwrap();
+
+    assert_eq!(
+        recorder.commands(),
+        vec![(Command::SetRamXAddressStartEndPosition as u8, vec![2, 5])]
+    );
+}
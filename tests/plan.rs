@@ -0,0 +1,196 @@
+extern crate cfap_screen;
+
+use cfap_screen::emulator::Emulator;
+use cfap_screen::*;
+
+/// Clears pixel (`x`, `y`) of a 200 by 200 image.
+fn set_black(image: &mut [u8], x: usize, y: usize) {
+    image[y * 25 + x / 8] &= !(0x80 >> (x % 8));
+}
+
+#[test]
+fn unchanged_frames() {
+    let old = vec![0xff; 5000];
+    let mut windows = [Window::default(); 4];
+
+    let plan = PlanPolicy::default().plan(200, 200, &old, &old, &mut windows);
+    assert_eq!(plan, Some(Plan::Unchanged));
+}
+
+#[test]
+fn nearby_changes_share_a_window() {
+    let old = vec![0xff; 5000];
+    let mut new = old.clone();
+    set_black(&mut new, 10, 20);
+    set_black(&mut new, 30, 21);
+    set_black(&mut new, 12, 23);
+    let mut windows = [Window::default(); 4];
+
+    let plan = PlanPolicy::default().plan(200, 200, &old, &new, &mut windows);
+    assert_eq!(plan, Some(Plan::Window(Window { x: 8, y: 20, w: 24, h: 4 })));
+}
+
+#[test]
+fn distant_changes_get_their_own_windows() {
+    let old = vec![0xff; 5000];
+    let mut new = old.clone();
+    for y in 0..20 {
+        set_black(&mut new, 0, y);
+        set_black(&mut new, 199, 180 + y);
+    }
+    let mut windows = [Window::default(); 4];
+
+    let plan = PlanPolicy::default().plan(200, 200, &old, &new, &mut windows);
+    assert_eq!(
+        plan,
+        Some(Plan::Windows(&[
+            Window { x: 0, y: 0, w: 8, h: 20 },
+            Window { x: 192, y: 180, w: 8, h: 20 },
+        ]))
+    );
+
+    // with one window allowed they are merged
+    let policy = PlanPolicy { max_windows: 1, ..PlanPolicy::default() };
+    let plan = policy.plan(200, 200, &old, &new, &mut windows);
+    assert_eq!(plan, Some(Plan::Window(Window { x: 0, y: 0, w: 200, h: 200 })));
+}
+
+#[test]
+fn large_or_costly_changes_are_full_refreshes() {
+    let old = vec![0xff; 5000];
+    let mut windows = [Window::default(); 4];
+
+    let mut new = old.clone();
+    for b in new[..3000].iter_mut() {
+        *b = 0x00;
+    }
+    let plan = PlanPolicy::default().plan(200, 200, &old, &new, &mut windows);
+    assert_eq!(plan, Some(Plan::Full));
+
+    let mut new = old.clone();
+    set_black(&mut new, 0, 0);
+    let policy = PlanPolicy { full_update_us: 300_000, ..PlanPolicy::default() };
+    let plan = policy.plan(200, 200, &old, &new, &mut windows);
+    assert_eq!(plan, Some(Plan::Full));
+
+    let plan = PlanPolicy::default().plan(200, 200, &old, &new, &mut []);
+    assert_eq!(plan, Some(Plan::Full));
+}
+
+#[test]
+fn plans_check_lengths() {
+    let old = vec![0xff; 5000];
+    let mut windows = [Window::default(); 4];
+
+    assert_eq!(PlanPolicy::default().plan(200, 200, &old, &old[1..], &mut windows), None);
+    assert_eq!(PlanPolicy::default().plan(200, 100, &old, &old, &mut windows), None);
+}
+
+#[test]
+fn show_plan() {
+    let (emulator, mut screen) =
+        Emulator::screen(ScreenBuilder::preset(Preset::CFAP200200A0_154)).unwrap();
+    let old = vec![0xff; 5000];
+    screen.show_full_screen_image(&old).unwrap();
+
+    let mut new = old.clone();
+    for y in 0..20 {
+        set_black(&mut new, 3, y);
+        set_black(&mut new, 197, 180 + y);
+    }
+    let mut windows = [Window::default(); 4];
+    let plan = PlanPolicy::default().plan(200, 200, &old, &new, &mut windows).unwrap();
+    screen.show_plan(&plan, &new).unwrap();
+
+    assert_eq!(emulator.image(), new);
+    assert!(emulator.errors().is_empty(), "{:?}", emulator.errors());
+
+    let bad = Plan::Window(Window { x: 192, y: 0, w: 16, h: 1 });
+    match screen.show_plan(&bad, &new) {
+        Err(ScreenError::BoundsError) => {}
+        _ => panic!("expected a bounds error"),
+    }
+}
+
+#[test]
+fn coalesce_aligns_clips_and_merges() {
+    let mut windows = [
+        // overlapping
+        Window { x: 3, y: 10, w: 10, h: 10 },
+        Window { x: 12, y: 15, w: 10, h: 10 },
+        // adjacent bytes, cheaper to merge than to set another area
+        Window { x: 100, y: 0, w: 4, h: 2 },
+        Window { x: 108, y: 0, w: 4, h: 2 },
+        // clipped
+        Window { x: 190, y: 190, w: 20, h: 20 },
+        // empty or off the screen
+        Window { x: 50, y: 50, w: 0, h: 5 },
+        Window { x: 200, y: 0, w: 8, h: 8 },
+    ];
+
+    let count = coalesce(&mut windows, 200, 200, 4);
+    assert_eq!(
+        windows[..count],
+        [
+            Window { x: 96, y: 0, w: 16, h: 2 },
+            Window { x: 0, y: 10, w: 24, h: 15 },
+            Window { x: 184, y: 190, w: 16, h: 10 },
+        ]
+    );
+}
+
+#[test]
+fn coalesce_limits_windows() {
+    let mut windows = [
+        Window { x: 0, y: 0, w: 8, h: 8 },
+        Window { x: 0, y: 100, w: 8, h: 8 },
+        Window { x: 0, y: 120, w: 8, h: 8 },
+    ];
+
+    let count = coalesce(&mut windows, 200, 200, 2);
+    assert_eq!(
+        windows[..count],
+        [Window { x: 0, y: 0, w: 8, h: 8 }, Window { x: 0, y: 100, w: 8, h: 28 }]
+    );
+
+    assert_eq!(coalesce(&mut windows, 200, 200, 0), 1);
+    assert_eq!(windows[0], Window { x: 0, y: 0, w: 8, h: 128 });
+}
+
+#[test]
+fn windows_near_the_largest_coordinates() {
+    let max = u16::MAX;
+    let edge = Window { x: max - 3, y: max - 1, w: 10, h: 10 };
+    let origin = Window { x: 0, y: 0, w: 8, h: 1 };
+
+    assert_eq!(edge.bytes(), 20);
+    assert!(edge.overlaps(&Window { x: max, y: max, w: 1, h: 1 }));
+    assert!(!edge.overlaps(&origin));
+    assert_eq!(edge.union(&edge), edge);
+    assert_eq!(edge.union(&origin), Window { x: 0, y: 0, w: max, h: max });
+
+    let mut windows = [edge, origin];
+    assert_eq!(coalesce(&mut windows, 200, 200, 4), 1);
+    assert_eq!(windows[0], origin);
+}
+
+#[test]
+fn windows_share_one_update() {
+    let (emulator, mut screen) =
+        Emulator::screen(ScreenBuilder::preset(Preset::CFAP200200A0_154)).unwrap();
+    let mut image = vec![0xff; 5000];
+    screen.show_full_screen_image(&image).unwrap();
+
+    set_black(&mut image, 3, 5);
+    set_black(&mut image, 150, 170);
+    let mut windows = [Window { x: 3, y: 5, w: 1, h: 1 }, Window { x: 150, y: 170, w: 1, h: 1 }];
+    let count = coalesce(&mut windows, 200, 200, 4);
+    assert_eq!(count, 2);
+
+    let updates = emulator.updates();
+    screen.update_windows(&image, &windows[..count]).unwrap();
+
+    assert_eq!(emulator.updates(), updates + 1);
+    assert_eq!(emulator.image(), image);
+    assert!(emulator.errors().is_empty(), "{:?}", emulator.errors());
+}
@@ -0,0 +1,58 @@
+extern crate cfap_screen;
+
+use cfap_screen::*;
+
+#[test]
+fn round_trip() {
+    let builder = ScreenBuilder::preset(Preset::CFAP200200A1_154)
+        .y_size(296)
+        .vcom(0x90)
+        .gate_scan(GateScan {
+            first_gate: FirstGate::G1,
+            order: GateOrder::Separated,
+            direction: ScanDirection::Reverse,
+        });
+
+    let profile = builder.to_profile();
+    assert_eq!(profile[..3], [b'C', b'F', PROFILE_VERSION]);
+
+    let decoded = ScreenBuilder::from_profile(&profile).unwrap();
+    assert_eq!(decoded.to_profile()[..], profile[..]);
+    assert_eq!(decoded.y_size, 296);
+    assert_eq!(decoded.gate_scan.direction, ScanDirection::Reverse);
+}
+
+#[test]
+fn rejects_corruption() {
+    let profile = ScreenBuilder::preset(Preset::CFAP200200A0_154).to_profile();
+
+    assert_eq!(
+        ScreenBuilder::from_profile(&profile[..PROFILE_LEN - 1]).err(),
+        Some(ProfileError::LengthError)
+    );
+
+    let mut bad = profile;
+    bad[0] = b'X';
+    assert_eq!(ScreenBuilder::from_profile(&bad).err(), Some(ProfileError::MagicError));
+
+    let mut bad = profile;
+    bad[2] = 2;
+    assert_eq!(ScreenBuilder::from_profile(&bad).err(), Some(ProfileError::VersionError(2)));
+
+    for i in 3..PROFILE_LEN {
+        let mut bad = profile;
+        bad[i] ^= 0x10;
+        assert_eq!(ScreenBuilder::from_profile(&bad).err(), Some(ProfileError::ChecksumError));
+    }
+}
+
+#[test]
+fn validates_decoded_config() {
+    let mut builder = ScreenBuilder::preset(Preset::CFAP200200A0_154);
+    builder.y_size = MAX_Y_SIZE + 1;
+
+    assert_eq!(
+        ScreenBuilder::from_profile(&builder.to_profile()).err(),
+        Some(ProfileError::ConfigError(ConfigError::YSize))
+    );
+}
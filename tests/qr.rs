@@ -0,0 +1,430 @@
+extern crate cfap_screen;
+
+use cfap_screen::qr::*;
+use cfap_screen::*;
+
+fn encode<'a>(data: &[u8], ecc: EccLevel, buf: &'a mut [u8]) -> Result<QrCode<'a>, QrError> {
+    let mut scratch = vec![0; buf.len()];
+    QrCode::encode(data, ecc, buf, &mut scratch)
+}
+
+/// The format information from the copy around the top left finder pattern.
+fn format_bits(code: &QrCode) -> u16 {
+    let mut modules = Vec::new();
+    modules.extend((0..6).map(|i| (8, i)));
+    modules.extend_from_slice(&[(8, 7), (8, 8), (7, 8)]);
+    modules.extend((9..15).map(|i| (14 - i, 8)));
+    modules.iter().enumerate().fold(0, |bits, (i, &(x, y))| {
+        bits | (code.module(x, y) as u16) << i
+    })
+}
+
+/// The same from the copy split between the other two finder patterns.
+fn format_bits_copy(code: &QrCode) -> u16 {
+    let size = code.size();
+    let mut modules = Vec::new();
+    modules.extend((0..8).map(|i| (size - 1 - i, 8)));
+    modules.extend((8..15).map(|i| (8, size - 15 + i)));
+    modules.iter().enumerate().fold(0, |bits, (i, &(x, y))| {
+        bits | (code.module(x, y) as u16) << i
+    })
+}
+
+/// Centers of the alignment patterns on each axis, by version, from table E.1
+/// of ISO/IEC 18004.
+const ALIGNMENT_POSITIONS: [&[u16]; 40] = [
+    &[],
+    &[6, 18],
+    &[6, 22],
+    &[6, 26],
+    &[6, 30],
+    &[6, 34],
+    &[6, 22, 38],
+    &[6, 24, 42],
+    &[6, 26, 46],
+    &[6, 28, 50],
+    &[6, 30, 54],
+    &[6, 32, 58],
+    &[6, 34, 62],
+    &[6, 26, 46, 66],
+    &[6, 26, 48, 70],
+    &[6, 26, 50, 74],
+    &[6, 30, 54, 78],
+    &[6, 30, 56, 82],
+    &[6, 30, 58, 86],
+    &[6, 34, 62, 90],
+    &[6, 28, 50, 72, 94],
+    &[6, 26, 50, 74, 98],
+    &[6, 30, 54, 78, 102],
+    &[6, 28, 54, 80, 106],
+    &[6, 32, 58, 84, 110],
+    &[6, 30, 58, 86, 114],
+    &[6, 34, 62, 90, 118],
+    &[6, 26, 50, 74, 98, 122],
+    &[6, 30, 54, 78, 102, 126],
+    &[6, 26, 52, 78, 104, 130],
+    &[6, 30, 56, 82, 108, 134],
+    &[6, 34, 60, 86, 112, 138],
+    &[6, 30, 58, 86, 114, 142],
+    &[6, 34, 62, 90, 118, 146],
+    &[6, 30, 54, 78, 102, 126, 150],
+    &[6, 24, 50, 76, 102, 128, 154],
+    &[6, 28, 54, 80, 106, 132, 158],
+    &[6, 32, 58, 84, 110, 136, 162],
+    &[6, 26, 54, 82, 110, 138, 166],
+    &[6, 30, 58, 86, 114, 142, 170],
+];
+
+fn alignment_positions(version: u8) -> &'static [u16] {
+    ALIGNMENT_POSITIONS[version as usize - 1]
+}
+
+/// Whether each module is part of a function pattern.
+fn function_modules(version: u8) -> Vec<Vec<bool>> {
+    let size = version as usize * 4 + 17;
+    let mut map = vec![vec![false; size]; size];
+    let mut mark = |x: usize, y: usize, w: usize, h: usize| {
+        for row in map.iter_mut().skip(y).take(h) {
+            for module in row.iter_mut().skip(x).take(w) {
+                *module = true;
+            }
+        }
+    };
+
+    mark(0, 0, 9, 9);
+    mark(size - 8, 0, 8, 9);
+    mark(0, size - 8, 9, 8);
+    mark(6, 0, 1, size);
+    mark(0, 6, size, 1);
+    let positions = alignment_positions(version);
+    for (i, &x) in positions.iter().enumerate() {
+        for (j, &y) in positions.iter().enumerate() {
+            let last = positions.len() - 1;
+            let corner = i == 0 && (j == 0 || j == last) || i == last && j == 0;
+            if !corner {
+                mark(x as usize - 2, y as usize - 2, 5, 5);
+            }
+        }
+    }
+    if version >= 7 {
+        mark(size - 11, 0, 3, 6);
+        mark(0, size - 11, 6, 3);
+    }
+    map
+}
+
+/// Reads the codewords in placement order, removing the mask.
+fn read_codewords(code: &QrCode) -> Vec<u8> {
+    let size = code.size() as usize;
+    let mask = format_bits(code) ^ 0x5412;
+    let mask = (mask >> 10) & 7;
+    let functions = function_modules(code.version());
+
+    let mut bits = Vec::new();
+    let mut right = size as i32 - 1;
+    while right >= 1 {
+        if right == 6 {
+            right = 5;
+        }
+        let upward = (right + 1) & 2 == 0;
+        for vert in 0..size {
+            let y = if upward { size - 1 - vert } else { vert };
+            for &x in [right as usize, right as usize - 1].iter() {
+                if functions[y][x] {
+                    continue;
+                }
+                let invert = match mask {
+                    0 => (x + y) % 2 == 0,
+                    1 => y % 2 == 0,
+                    2 => x % 3 == 0,
+                    3 => (x + y) % 3 == 0,
+                    4 => (x / 3 + y / 2) % 2 == 0,
+                    5 => x * y % 2 + x * y % 3 == 0,
+                    6 => (x * y % 2 + x * y % 3) % 2 == 0,
+                    _ => ((x + y) % 2 + x * y % 3) % 2 == 0,
+                };
+                bits.push(code.module(x as u16, y as u16) != invert);
+            }
+        }
+        right -= 2;
+    }
+
+    bits.chunks(8)
+        .filter(|byte| byte.len() == 8)
+        .map(|byte| byte.iter().fold(0, |b, &bit| b << 1 | bit as u8))
+        .collect()
+}
+
+fn gf_mul(x: u8, y: u8) -> u8 {
+    let (mut x, mut y, mut z) = (x as u16, y, 0u16);
+    while y != 0 {
+        if y & 1 != 0 {
+            z ^= x;
+        }
+        x <<= 1;
+        if x & 0x100 != 0 {
+            x ^= 0x11d;
+        }
+        y >>= 1;
+    }
+    z as u8
+}
+
+/// Splits interleaved codewords into blocks, checks each block's error
+/// correction, and returns the data codewords in order.
+fn check_blocks(codewords: &[u8], blocks: usize, ecc_len: usize) -> Vec<u8> {
+    let data_len = codewords.len() - blocks * ecc_len;
+    let short_len = data_len / blocks;
+    let short_blocks = blocks - data_len % blocks;
+
+    let mut split = vec![Vec::new(); blocks];
+    let mut iter = codewords.iter();
+    for j in 0..=short_len {
+        for (b, block) in split.iter_mut().enumerate() {
+            if j < short_len || b >= short_blocks {
+                block.push(*iter.next().unwrap());
+            }
+        }
+    }
+    for _ in 0..ecc_len {
+        for block in split.iter_mut() {
+            block.push(*iter.next().unwrap());
+        }
+    }
+
+    let mut data = Vec::new();
+    for block in split {
+        // the block is a multiple of the generator, so has its roots
+        let mut root = 1;
+        for _ in 0..ecc_len {
+            let syndrome = block.iter().fold(0, |s, &c| gf_mul(s, root) ^ c);
+            assert_eq!(syndrome, 0);
+            root = gf_mul(root, 2);
+        }
+        data.extend_from_slice(&block[..block.len() - ecc_len]);
+    }
+    data
+}
+
+/// Decodes a byte mode segment.
+fn read_bytes(data: &[u8], version: u8) -> Vec<u8> {
+    let bit = |i: usize| (data[i / 8] >> (7 - i % 8)) & 1;
+    let read = |start: usize, bits: usize| {
+        (start..start + bits).fold(0, |v, i| v << 1 | bit(i) as usize)
+    };
+
+    assert_eq!(read(0, 4), 0b0100);
+    let count_bits = if version <= 9 { 8 } else { 16 };
+    let len = read(4, count_bits);
+    (0..len).map(|i| read(4 + count_bits + i * 8, 8) as u8).collect()
+}
+
+#[test]
+fn smallest_version_that_fits() {
+    let mut buf = vec![0; MAX_BUFFER_LEN];
+    let cases = [
+        (1, EccLevel::Low, 1),
+        (17, EccLevel::Low, 1),
+        (18, EccLevel::Low, 2),
+        (14, EccLevel::Medium, 1),
+        (15, EccLevel::Medium, 2),
+        (2953, EccLevel::Low, 40),
+        (1273, EccLevel::High, 40),
+    ];
+    for &(len, ecc, version) in cases.iter() {
+        let code = encode(&vec![b'a'; len], ecc, &mut buf).unwrap();
+        assert_eq!(code.version(), version, "{} bytes at {:?}", len, ecc);
+        assert_eq!(code.size(), version as u16 * 4 + 17);
+    }
+
+    assert_eq!(encode(&[b'a'; 2954], EccLevel::Low, &mut buf).err(), Some(QrError::LengthError));
+    let mut buf = vec![0; buffer_len(1)];
+    assert_eq!(encode(&[b'a'; 18], EccLevel::Low, &mut buf).err(), Some(QrError::BufferError));
+}
+
+#[test]
+fn function_patterns() {
+    let mut buf = vec![0; MAX_BUFFER_LEN];
+    let code = encode(b"https://example.com/provision", EccLevel::Medium, &mut buf).unwrap();
+    let size = code.size();
+
+    for &(left, top) in [(0, 0), (size - 7, 0), (0, size - 7)].iter() {
+        for y in 0..7 {
+            for x in 0..7 {
+                let ring = x.max(y).max(6 - x).max(6 - y);
+                let distance = ring - 3;
+                assert_eq!(code.module(left + x, top + y), distance != 2, "({}, {})", x, y);
+            }
+        }
+    }
+    for i in 8..size - 8 {
+        assert_eq!(code.module(i, 6), i % 2 == 0);
+        assert_eq!(code.module(6, i), i % 2 == 0);
+    }
+    assert!(code.module(8, size - 8));
+}
+
+#[test]
+fn format_information() {
+    // the 32 valid format strings, by level, L M Q H, and mask
+    let formats: [[u16; 8]; 4] = [
+        [0x77c4, 0x72f3, 0x7daa, 0x789d, 0x662f, 0x6318, 0x6c41, 0x6976],
+        [0x5412, 0x5125, 0x5e7c, 0x5b4b, 0x45f9, 0x40ce, 0x4f97, 0x4aa0],
+        [0x355f, 0x3068, 0x3f31, 0x3a06, 0x24b4, 0x2183, 0x2eda, 0x2bed],
+        [0x1689, 0x13be, 0x1ce7, 0x19d0, 0x0762, 0x0255, 0x0d0c, 0x083b],
+    ];
+    let levels = [EccLevel::Low, EccLevel::Medium, EccLevel::Quartile, EccLevel::High];
+
+    let mut buf = vec![0; MAX_BUFFER_LEN];
+    for (l, &ecc) in levels.iter().enumerate() {
+        let code = encode(b"format", ecc, &mut buf).unwrap();
+        let bits = format_bits(&code);
+        assert!(formats[l].contains(&bits), "{:?}: {:#06x}", ecc, bits);
+        assert_eq!(format_bits_copy(&code), bits);
+    }
+}
+
+#[test]
+fn version_information() {
+    let mut buf = vec![0; MAX_BUFFER_LEN];
+    let code = encode(&[0x55; 150], EccLevel::Low, &mut buf).unwrap();
+    assert_eq!(code.version(), 7);
+
+    let size = code.size();
+    let (mut below, mut right) = (0u32, 0u32);
+    for i in 0..18 {
+        let (a, b) = (size - 11 + i % 3, i / 3);
+        right |= (code.module(a, b) as u32) << i;
+        below |= (code.module(b, a) as u32) << i;
+    }
+    assert_eq!((right, below), (0x07c94, 0x07c94));
+}
+
+#[test]
+fn codes_read_back() {
+    let cases: [(&[u8], EccLevel, u8, usize, usize); 3] = [
+        (b"WIFI:S:panel;T:WPA;P:secret;;", EccLevel::Medium, 3, 1, 26),
+        (&[0xa5; 50], EccLevel::Quartile, 5, 4, 18),
+        (&[0x3c; 150], EccLevel::Low, 7, 2, 20),
+    ];
+
+    let mut buf = vec![0; MAX_BUFFER_LEN];
+    for &(data, ecc, version, blocks, ecc_len) in cases.iter() {
+        let code = encode(data, ecc, &mut buf).unwrap();
+        assert_eq!(code.version(), version);
+
+        let codewords = read_codewords(&code);
+        let data_codewords = check_blocks(&codewords, blocks, ecc_len);
+        assert_eq!(read_bytes(&data_codewords, version), data);
+    }
+}
+
+/// Symbols from a port of Nayuki's QR Code generator, dark modules as `#`.
+const HELLO_WORLD_L: [&str; 21] = [
+    "#######...###.#######",
+    "#.....#.###.#.#.....#",
+    "#.###.#...###.#.###.#",
+    "#.###.#.##..#.#.###.#",
+    "#.###.#..#..#.#.###.#",
+    "#.....#.#..#..#.....#",
+    "#######.#.#.#.#######",
+    ".........#...........",
+    "#####.###..#.#.#.#.#.",
+    "#...##..#.#.##..###.#",
+    "##.##.#.#.#.###..###.",
+    ".##..#.##..###.#.##..",
+    "..#####.#...#.##....#",
+    "........##...#####...",
+    "#######.#...####..##.",
+    "#.....#..#..##.#.###.",
+    "#.###.#.#.#####.#..##",
+    "#.###.#.###....###...",
+    "#.###.#.#..##.##..#..",
+    "#.....#.###.##..###..",
+    "#######.##.#..#.#..#.",
+];
+
+const WIFI_M: [&str; 29] = [
+    "#######.#......##.###.#######",
+    "#.....#.#...#.##.##...#.....#",
+    "#.###.#..#.#####..#...#.###.#",
+    "#.###.#.##.##...##..#.#.###.#",
+    "#.###.#..#...#...#.#..#.###.#",
+    "#.....#...#...#..#.#..#.....#",
+    "#######.#.#.#.#.#.#.#.#######",
+    "........#.#..##..####........",
+    "#.##.###.....##.###.#.#..#.##",
+    "#.#.##..#.##...##..#..#.#.##.",
+    ".##.#.#.#..##.##....#.##.###.",
+    "...##..##....###...#.#.###...",
+    "##.#..####.#....##..#....##..",
+    "..#.##.#.###.#...#...#.......",
+    "#.#######...#.#...######.####",
+    "#.#.#.......##.#...#.#.###...",
+    ".###.##..#.#......##...####.#",
+    ".##..#..#.##.#....#..#.#.#..#",
+    "#.#.#.###.#.........###..##..",
+    "..###..###.....###..#.#.#.###",
+    ".#....###.#.##.#.#.######.###",
+    "........#..#.###.#..#...#.#..",
+    "#######.#..###..#...#.#.#..#.",
+    "#.....#.##.#....#.#.#...#..##",
+    "#.###.#...###.##.##.#######.#",
+    "#.###.#.#.#.#.#.#.#.##.##.##.",
+    "#.###.#.#..#..####.#.###.#..#",
+    "#.....#.....##..#..#...###.#.",
+    "#######.#.##.#.#.#.#####...#.",
+];
+
+/// Checks the code for `data` against `reference`, masked as given by
+/// `format`, the format information.
+fn assert_matches(data: &[u8], ecc: EccLevel, reference: &[&str], format: u16) {
+    let mut buf = vec![0; MAX_BUFFER_LEN];
+    let code = encode(data, ecc, &mut buf).unwrap();
+    assert_eq!(format_bits(&code), format, "{:?}", ecc);
+
+    let rows: Vec<String> = (0..code.size())
+        .map(|y| (0..code.size()).map(|x| if code.module(x, y) { '#' } else { '.' }).collect())
+        .collect();
+    assert_eq!(rows, reference, "{:?}", ecc);
+}
+
+#[test]
+fn codes_match_reference_symbols() {
+    // version 1 with mask 2, and version 3 with mask 3
+    assert_matches(b"Hello, world!", EccLevel::Low, &HELLO_WORLD_L, 0x7daa);
+    assert_matches(b"WIFI:S:panel;T:WPA;P:secret;;", EccLevel::Medium, &WIFI_M, 0x5b4b);
+}
+
+#[test]
+fn codes_are_drawn_centered_with_a_quiet_zone() {
+    let mut buf = vec![0; MAX_BUFFER_LEN];
+    let code = encode(b"hello", EccLevel::Low, &mut buf).unwrap();
+    assert_eq!(code.scale_for(200, 100, QUIET_ZONE), 3);
+
+    let mut image = vec![0x00; image_len(200, 100)];
+    let scale = code.draw(&mut Canvas::new(&mut image, 200), QUIET_ZONE).unwrap();
+    assert_eq!(scale, 3);
+
+    // 29 modules of 3 pixels with the quiet zone, centered
+    let canvas = Canvas::new(&mut image, 200);
+    let (left, top) = ((200 - 87) / 2, (100 - 87) / 2);
+    for y in 0..100 {
+        for x in 0..200 {
+            let inside = x >= left && x < left + 87 && y >= top && y < top + 87;
+            let module = inside && x >= left + 12 && y >= top + 12
+                && code.module((x - left - 12) / 3, (y - top - 12) / 3);
+            // the canvas started black
+            let expected = if inside && !module { Color::White } else { Color::Black };
+            assert_eq!(canvas.pixel(x, y), Some(expected), "({}, {})", x, y);
+        }
+    }
+
+    // a band draws the same rows
+    let mut band = vec![0x00; image_len(200, 10)];
+    code.draw(&mut Canvas::new_band(&mut band, 200, 100, 40), QUIET_ZONE).unwrap();
+    assert_eq!(band[..], image[image_len(200, 40)..image_len(200, 50)]);
+
+    let mut small = vec![0x00; image_len(28, 28)];
+    assert_eq!(code.draw(&mut Canvas::new(&mut small, 28), QUIET_ZONE), Err(QrError::SizeError));
+}
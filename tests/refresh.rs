@@ -0,0 +1,95 @@
+extern crate cfap_screen;
+
+use cfap_screen::mock::{MockScreen, Recorder};
+use cfap_screen::Refresh::{Full, Partial};
+use cfap_screen::*;
+
+fn new_screen() -> (Recorder, MockScreen) {
+    Recorder::screen(ScreenBuilder::preset(Preset::CFAP200200A0_154)).unwrap()
+}
+
+fn policy(updates: Option<u16>, ms: Option<u32>, pixels: Option<u32>) -> RefreshPolicy {
+    RefreshPolicy {
+        max_partial_updates: updates,
+        max_elapsed_ms: ms,
+        max_changed_pixels: pixels,
+    }
+}
+
+fn run(tracker: &mut RefreshTracker, updates: &[(u32, u32)]) -> Vec<Refresh> {
+    updates
+        .iter()
+        .map(|&(ms, pixels)| {
+            tracker.tick(ms);
+            let refresh = tracker.next(pixels);
+            tracker.record(refresh, pixels);
+            refresh
+        })
+        .collect()
+}
+
+#[test]
+fn first_update_is_full() {
+    let mut tracker = RefreshTracker::new(RefreshPolicy::NEVER_FULL);
+
+    assert_eq!(run(&mut tracker, &[(0, 0), (0, 0), (0, 0)]), vec![Full, Partial, Partial]);
+}
+
+#[test]
+fn partial_update_count() {
+    let mut tracker = RefreshTracker::new(policy(Some(2), None, None));
+
+    assert_eq!(
+        run(&mut tracker, &[(0, 0); 7]),
+        vec![Full, Partial, Partial, Full, Partial, Partial, Full]
+    );
+}
+
+#[test]
+fn elapsed_time() {
+    let mut tracker = RefreshTracker::new(policy(None, Some(1000), None));
+
+    assert_eq!(
+        run(&mut tracker, &[(0, 0), (600, 0), (400, 0), (1, 0), (999, 0)]),
+        vec![Full, Partial, Partial, Full, Partial]
+    );
+}
+
+#[test]
+fn changed_area() {
+    let mut tracker = RefreshTracker::new(policy(None, None, Some(100)));
+
+    assert_eq!(
+        run(&mut tracker, &[(0, 5000), (0, 60), (0, 40), (0, 1), (0, 101)]),
+        vec![Full, Partial, Partial, Full, Full]
+    );
+}
+
+#[test]
+fn forced_full() {
+    let mut tracker = RefreshTracker::new(RefreshPolicy::NEVER_FULL);
+    run(&mut tracker, &[(0, 0)]);
+
+    tracker.force_full();
+
+    assert_eq!(run(&mut tracker, &[(0, 0), (0, 0)]), vec![Full, Partial]);
+    assert_eq!(tracker.partial_updates(), 1);
+}
+
+#[test]
+fn screen_refresh_promotes_to_full() {
+    let (recorder, mut screen) = new_screen();
+    let mut tracker = RefreshTracker::new(policy(Some(1), None, None));
+    tracker.record(Refresh::Full, 0);
+    recorder.clear();
+
+    assert_eq!(screen.refresh(&mut tracker, 64).unwrap(), Partial);
+    assert_eq!(screen.refresh(&mut tracker, 64).unwrap(), Full);
+
+    let commands = recorder.commands();
+    let luts: Vec<&[u8]> = commands.iter().filter(|c| c.0 == 0x32).map(|c| &c.1[..]).collect();
+    assert_eq!(luts, vec![&LUT_PART_CFAP200200A0_154[..], &LUT_FULL_CFAP200200A0_154[..]]);
+    let sequences: Vec<u8> = commands.iter().filter(|c| c.0 == 0x22).map(|c| c.1[0]).collect();
+    assert_eq!(sequences, vec![0xc0, 0x04, 0xc3, 0xc0, 0xc7, 0xc3]);
+    assert_eq!(tracker.changed_pixels(), 0);
+}
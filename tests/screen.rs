@@ -0,0 +1,511 @@
+extern crate cfap_screen;
+
+use cfap_screen::mock::{Event, MockScreen, Pin, Recorder};
+use cfap_screen::*;
+
+fn a0() -> ScreenBuilder {
+    ScreenBuilder::preset(Preset::CFAP200200A0_154)
+}
+
+#[test]
+fn init_resets_then_configures() {
+    let (recorder, _) = Recorder::screen(a0()).unwrap();
+
+    assert_eq!(
+        recorder.events()[..6],
+        [
+            Event::Low(Pin::Reset),
+            Event::High(Pin::Cs),
+            Event::High(Pin::Dc),
+            Event::Delay(10),
+            Event::High(Pin::Reset),
+            Event::Delay(10),
+        ]
+    );
+    assert_eq!(
+        recorder.commands(),
+        vec![
+            (0x01, vec![0xc7, 0x00, 0x00]),
+            (0x0c, vec![0xd7, 0xd6, 0x9d]),
+            (0x2c, vec![0xa8]),
+            (0x3a, vec![0x1a]),
+            (0x3b, vec![0x08]),
+            (0x11, vec![0x01]),
+        ]
+    );
+}
+
+#[test]
+fn init_uses_preset_settings() {
+    let (recorder, _) = Recorder::screen(ScreenBuilder::preset(Preset::CFAP200200A1_154)).unwrap();
+
+    assert_eq!(recorder.commands()[2], (0x2c, vec![0x7f]));
+}
+
+#[test]
+fn every_command_is_framed_by_cs() {
+    let (recorder, _) = Recorder::screen(a0()).unwrap();
+
+    let frames = recorder.frames();
+    assert_eq!(frames.len(), 6);
+    for frame in frames {
+        assert!(!frame[0].0);
+        assert!(frame[1..].iter().all(|&(dc, _)| dc));
+    }
+    assert_eq!(recorder.events().last(), Some(&Event::High(Pin::Cs)));
+}
+
+#[test]
+fn gate_count_follows_y_size() {
+    let (recorder, _) = Recorder::screen(a0().x_size(128).y_size(296)).unwrap();
+
+    assert_eq!(recorder.commands()[0], (0x01, vec![0x27, 0x01, 0x00]));
+}
+
+#[test]
+fn invalid_configs_are_rejected_before_any_io() {
+    let cases = vec![
+        (a0().x_size(0), ConfigError::XSize),
+        (a0().x_size(MAX_X_SIZE + 1), ConfigError::XSize),
+        (a0().y_size(0), ConfigError::YSize),
+        (a0().y_size(MAX_Y_SIZE + 1), ConfigError::YSize),
+        (a0().dummy_line(MAX_DUMMY_LINE + 1), ConfigError::DummyLine),
+        (a0().gate_line(MAX_GATE_LINE + 1), ConfigError::GateLine),
+    ];
+
+    for (builder, expected) in cases {
+        let recorder = Recorder::new();
+        let result = builder.new_screen(
+            recorder.spi(),
+            recorder.dc(),
+            recorder.cs(),
+            recorder.busy(),
+            recorder.reset(),
+            &mut recorder.delay(),
+        );
+
+        match result {
+            Err(ScreenError::ConfigError(err)) => assert_eq!(err, expected),
+            _ => panic!("expected {:?}", expected),
+        }
+        assert!(recorder.events().is_empty());
+    }
+}
+
+#[test]
+fn fields_set_directly_are_validated() {
+    let mut builder = a0();
+    builder.dummy_line = MAX_DUMMY_LINE + 1;
+
+    let recorder = Recorder::new();
+    let size = DynamicSize { x_size: 200, y_size: 200 };
+    let result = builder.new_sized_screen(
+        size,
+        recorder.spi(),
+        recorder.dc(),
+        recorder.cs(),
+        recorder.busy(),
+        recorder.reset(),
+        &mut recorder.delay(),
+    );
+
+    match result {
+        Err(ScreenError::ConfigError(ConfigError::DummyLine)) => {}
+        _ => panic!("expected a dummy line error"),
+    }
+    assert!(recorder.events().is_empty());
+}
+
+#[test]
+fn gate_scan_bits_are_written() {
+    let gate_scan = GateScan {
+        first_gate: FirstGate::G1,
+        order: GateOrder::Separated,
+        direction: ScanDirection::Forward,
+    };
+    let (recorder, _) = Recorder::screen(a0().gate_scan(gate_scan)).unwrap();
+
+    assert_eq!(recorder.commands()[0], (0x01, vec![0xc7, 0x00, 0b110]));
+    assert_eq!(recorder.commands()[5], (0x11, vec![0x01]));
+}
+
+#[test]
+fn reverse_scan_flips_entry_mode_y() {
+    let gate_scan = GateScan {
+        direction: ScanDirection::Reverse,
+        ..GateScan::POR
+    };
+    let (recorder, _) = Recorder::screen(a0().gate_scan(gate_scan)).unwrap();
+
+    assert_eq!(recorder.commands()[0], (0x01, vec![0xc7, 0x00, 0b001]));
+    assert_eq!(recorder.commands()[5], (0x11, vec![0x03]));
+}
+
+#[test]
+fn set_display_area() {
+    let (recorder, mut screen) = Recorder::screen(a0()).unwrap();
+    recorder.clear();
+
+    screen.set_display_area(2, 5, 150, 10).unwrap();
+
+    assert_eq!(
+        recorder.commands(),
+        vec![
+            (0x44, vec![2, 5]),
+            (0x45, vec![150, 0, 10, 0]),
+            (0x4e, vec![2]),
+            (0x4f, vec![150, 0]),
+        ]
+    );
+}
+
+#[test]
+fn set_display_area_splits_nine_bit_y() {
+    let (recorder, mut screen) = Recorder::screen(a0().y_size(300)).unwrap();
+    recorder.clear();
+
+    screen.set_display_area(0, 24, 299, 256).unwrap();
+
+    assert_eq!(recorder.commands()[1], (0x45, vec![0x2b, 0x01, 0x00, 0x01]));
+    assert_eq!(recorder.commands()[3], (0x4f, vec![0x2b, 0x01]));
+}
+
+#[test]
+fn set_display_area_mirrors_y_for_reverse_scan() {
+    let gate_scan = GateScan {
+        direction: ScanDirection::Reverse,
+        ..GateScan::POR
+    };
+    let (recorder, mut screen) = Recorder::screen(a0().gate_scan(gate_scan)).unwrap();
+    recorder.clear();
+
+    screen.set_display_area(0, 24, 199, 0).unwrap();
+
+    assert_eq!(recorder.commands()[1], (0x45, vec![0, 0, 199, 0]));
+    assert_eq!(recorder.commands()[3], (0x4f, vec![0, 0]));
+}
+
+#[test]
+fn set_display_area_bounds() {
+    let (recorder, mut screen) = Recorder::screen(a0()).unwrap();
+    recorder.clear();
+
+    for &(x_start, x_end, y_start, y_end) in &[(25, 25, 0, 0), (0, 25, 0, 0), (0, 0, 200, 0), (0, 0, 0, 200)] {
+        match screen.set_display_area(x_start, x_end, y_start, y_end) {
+            Err(ScreenError::BoundsError) => {}
+            _ => panic!("expected a bounds error"),
+        }
+    }
+    assert!(recorder.events().is_empty());
+}
+
+fn full_update_commands(image: &[u8], lut: [u8; 30]) -> Vec<(u8, Vec<u8>)> {
+    vec![
+        (0x32, lut.to_vec()),
+        (0x22, vec![0xc0]),
+        (0x20, vec![]),
+        (0x44, vec![0, 24]),
+        (0x45, vec![199, 0, 0, 0]),
+        (0x4e, vec![0]),
+        (0x4f, vec![199, 0]),
+        (0x24, image.to_vec()),
+        (0x22, vec![0xc7]),
+        (0x20, vec![]),
+        (0xff, vec![]),
+        (0x22, vec![0xc3]),
+        (0x20, vec![]),
+    ]
+}
+
+#[test]
+fn show_full_screen_image() {
+    let (recorder, mut screen) = Recorder::screen(a0()).unwrap();
+    recorder.clear();
+
+    let image: Vec<u8> = (0..5000).map(|i| i as u8).collect();
+    screen.show_full_screen_image(&image).unwrap();
+
+    assert_eq!(recorder.commands(), full_update_commands(&image, LUT_FULL_CFAP200200A0_154));
+}
+
+#[test]
+fn show_full_screen_image_checks_length() {
+    let (recorder, mut screen) = Recorder::screen(a0()).unwrap();
+    recorder.clear();
+
+    match screen.show_full_screen_image(&[0; 4999]) {
+        Err(ScreenError::LengthError) => {}
+        _ => panic!("expected a length error"),
+    }
+    assert!(recorder.events().is_empty());
+}
+
+#[test]
+fn show_full_screen_image_fixed_size() {
+    let recorder = Recorder::new();
+    let mut screen: MockScreen<CFAP200200A0_154Size> = CFAP200200A0_154::builder()
+        .new_sized_screen(
+            CFAP200200A0_154::size(),
+            recorder.spi(),
+            recorder.dc(),
+            recorder.cs(),
+            recorder.busy(),
+            recorder.reset(),
+            &mut recorder.delay(),
+        )
+        .unwrap();
+    recorder.clear();
+
+    let image = [0xaa; CFAP200200A0_154Size::LEN];
+    screen.show_full_screen_image(&image).unwrap();
+
+    assert_eq!(recorder.commands(), full_update_commands(&image, LUT_FULL_CFAP200200A0_154));
+}
+
+#[test]
+fn sized_screen_uses_builder_size() {
+    let recorder = Recorder::new();
+    let screen: MockScreen<FixedSize<128, 296, { image_len(128, 296) }>> = a0()
+        .x_size(128)
+        .y_size(296)
+        .new_sized_screen(
+            FixedSize::new(),
+            recorder.spi(),
+            recorder.dc(),
+            recorder.cs(),
+            recorder.busy(),
+            recorder.reset(),
+            &mut recorder.delay(),
+        )
+        .unwrap();
+
+    assert_eq!((screen.x_size(), screen.y_size()), (128, 296));
+    assert_eq!(recorder.commands()[0], (0x01, vec![0x27, 0x01, 0x00]));
+}
+
+#[test]
+fn sized_screen_rejects_a_different_builder_size() {
+    let recorder = Recorder::new();
+    let result: Result<MockScreen<FixedSize<128, 296, { image_len(128, 296) }>>, _> = a0()
+        .new_sized_screen(
+            FixedSize::new(),
+            recorder.spi(),
+            recorder.dc(),
+            recorder.cs(),
+            recorder.busy(),
+            recorder.reset(),
+            &mut recorder.delay(),
+        );
+
+    match result {
+        Err(ScreenError::BoundsError) => {}
+        _ => panic!("expected a bounds error"),
+    }
+    assert!(recorder.events().is_empty());
+}
+
+#[test]
+fn load_partial_image() {
+    let (recorder, mut screen) = Recorder::screen(a0()).unwrap();
+    recorder.clear();
+
+    screen.load_partial_image(3, 2, 40, 3, &[1, 2, 3, 4, 5, 6]).unwrap();
+
+    assert_eq!(
+        recorder.commands(),
+        vec![
+            (0x44, vec![3, 4]),
+            (0x45, vec![40, 0, 42, 0]),
+            (0x4e, vec![3]),
+            (0x4f, vec![40, 0]),
+            (0x24, vec![1, 2, 3, 4, 5, 6]),
+        ]
+    );
+}
+
+#[test]
+fn load_partial_image_checks_length() {
+    let (recorder, mut screen) = Recorder::screen(a0()).unwrap();
+    recorder.clear();
+
+    match screen.load_partial_image(3, 2, 40, 3, &[1, 2, 3, 4, 5]) {
+        Err(ScreenError::LengthError) => {}
+        _ => panic!("expected a length error"),
+    }
+    assert!(recorder.events().is_empty());
+}
+
+#[test]
+fn empty_partial_image_is_not_written() {
+    let (recorder, mut screen) = Recorder::screen(a0()).unwrap();
+    recorder.clear();
+
+    screen.show_partial_image(0, 0, 0, 0, &[]).unwrap();
+    screen.show_partial_image(3, 2, 40, 0, &[]).unwrap();
+
+    assert!(recorder.events().is_empty());
+}
+
+#[test]
+fn partial_image_past_the_address_range_is_a_bounds_error() {
+    let (recorder, mut screen) = Recorder::screen(a0()).unwrap();
+    recorder.clear();
+
+    match screen.show_partial_image(200, 100, 0, 1, &[0; 100]) {
+        Err(ScreenError::BoundsError) => {}
+        _ => panic!("expected a bounds error"),
+    }
+    match screen.load_partial_image(0, 1, 0xff00, 0x200, &[0; 0x200]) {
+        Err(ScreenError::BoundsError) => {}
+        _ => panic!("expected a bounds error"),
+    }
+    assert!(recorder.events().is_empty());
+}
+
+#[test]
+fn show_partial_image_writes_the_image_again() {
+    let (recorder, mut screen) = Recorder::screen(a0()).unwrap();
+    recorder.clear();
+
+    screen.show_partial_image(3, 2, 40, 1, &[1, 2]).unwrap();
+
+    let load = vec![
+        (0x44, vec![3, 4]),
+        (0x45, vec![40, 0, 40, 0]),
+        (0x4e, vec![3]),
+        (0x4f, vec![40, 0]),
+        (0x24, vec![1, 2]),
+    ];
+    let mut expected = load.clone();
+    expected.extend(vec![
+        (0x32, LUT_PART_CFAP200200A0_154.to_vec()),
+        (0x22, vec![0xc0]),
+        (0x20, vec![]),
+        (0x22, vec![0x04]),
+        (0x20, vec![]),
+        (0xff, vec![]),
+        (0x22, vec![0xc3]),
+        (0x20, vec![]),
+    ]);
+    expected.extend(load);
+    assert_eq!(recorder.commands(), expected);
+}
+
+#[test]
+fn load_luts() {
+    let (recorder, mut screen) =
+        Recorder::screen(ScreenBuilder::preset(Preset::CFAP200200A1_154)).unwrap();
+    recorder.clear();
+
+    screen.load_full_update_lut().unwrap();
+    screen.load_partial_update_lut().unwrap();
+
+    assert_eq!(
+        recorder.commands(),
+        vec![
+            (0x32, LUT_FULL_CFAP200200A1_154.to_vec()),
+            (0x32, LUT_PART_CFAP200200A1_154.to_vec()),
+        ]
+    );
+}
+
+#[test]
+fn power_on_and_off_wait_for_busy() {
+    let (recorder, mut screen) = Recorder::screen(a0()).unwrap();
+    recorder.clear();
+
+    recorder.set_busy_polls(3);
+    screen.power_on().unwrap();
+    assert_eq!(recorder.busy_polls(), 0);
+
+    recorder.set_busy_polls(3);
+    screen.power_off().unwrap();
+    assert_eq!(recorder.busy_polls(), 0);
+
+    assert_eq!(
+        recorder.commands(),
+        vec![(0x22, vec![0xc0]), (0x20, vec![]), (0x22, vec![0xc3]), (0x20, vec![])]
+    );
+}
+
+#[test]
+fn updates() {
+    let (recorder, mut screen) = Recorder::screen(a0()).unwrap();
+    recorder.clear();
+
+    recorder.set_busy_polls(5);
+    screen.update_full().unwrap();
+    assert_eq!(recorder.busy_polls(), 0);
+    screen.update_partial().unwrap();
+
+    assert_eq!(
+        recorder.commands(),
+        vec![
+            (0x22, vec![0xc7]),
+            (0x20, vec![]),
+            (0xff, vec![]),
+            (0x22, vec![0x04]),
+            (0x20, vec![]),
+            (0xff, vec![]),
+        ]
+    );
+}
+
+#[test]
+fn load_image_is_one_frame() {
+    let (recorder, mut screen) = Recorder::screen(a0()).unwrap();
+    recorder.clear();
+
+    screen.load_image(&[0x12, 0x34]).unwrap();
+
+    assert_eq!(recorder.frames(), vec![vec![(false, 0x24), (true, 0x12), (true, 0x34)]]);
+}
+
+#[test]
+fn raw_writes() {
+    let (recorder, mut screen) = Recorder::screen(a0()).unwrap();
+    recorder.clear();
+
+    screen.write_cmd(Command::SwReset).unwrap();
+    screen.write_cmd_string(Command::DeepSleepMode, &[0x01]).unwrap();
+    screen.write_data(0x02).unwrap();
+
+    assert_eq!(
+        recorder.frames(),
+        vec![vec![(false, 0x12)], vec![(false, 0x10), (true, 0x01)], vec![(true, 0x02)]]
+    );
+    assert_eq!(recorder.commands(), vec![(0x12, vec![]), (0x10, vec![0x01, 0x02])]);
+}
+
+#[test]
+fn unlisted_opcodes() {
+    let (recorder, mut screen) = Recorder::screen(a0()).unwrap();
+    recorder.clear();
+
+    screen.write_raw(0x26, &[0xaa, 0x55]).unwrap();
+    recorder.queue_miso(&[0x00, 0x19, 0x10]);
+    let mut temperature = [0; 2];
+    screen.read_raw(0x1b, &mut temperature).unwrap();
+
+    assert_eq!(temperature, [0x19, 0x10]);
+    assert_eq!(
+        recorder.frames(),
+        vec![
+            vec![(false, 0x26), (true, 0xaa), (true, 0x55)],
+            vec![(false, 0x1b), (true, 0x00), (true, 0x00)],
+        ]
+    );
+}
+
+#[test]
+fn command_opcode_conversions() {
+    use std::convert::TryFrom;
+
+    for opcode in 0..=0xffu8 {
+        if let Ok(cmd) = Command::try_from(opcode) {
+            assert_eq!(u8::from(cmd), opcode);
+        }
+    }
+    assert!(matches!(Command::try_from(0x4f), Ok(Command::SetRamYAddressCounter)));
+    assert_eq!(Command::try_from(0x2f).unwrap_err(), UnknownOpcode(0x2f));
+}
@@ -0,0 +1,170 @@
+extern crate cfap_screen;
+extern crate embedded_hal;
+extern crate nb;
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+use cfap_screen::emulator::Emulator;
+use cfap_screen::mock::{MockSpi, Recorder};
+use cfap_screen::*;
+use embedded_hal::spi::FullDuplex;
+
+/// Fails every send once `fail` is set.
+struct FailingSpi {
+    spi: MockSpi,
+    fail: Rc<Cell<bool>>,
+}
+
+impl FullDuplex<u8> for FailingSpi {
+    type Error = ();
+
+    fn read(&mut self) -> nb::Result<u8, ()> {
+        Ok(self.spi.read().unwrap())
+    }
+
+    fn send(&mut self, byte: u8) -> nb::Result<(), ()> {
+        if self.fail.get() {
+            return Err(nb::Error::Other(()));
+        }
+        self.spi.send(byte).unwrap();
+        Ok(())
+    }
+}
+
+#[test]
+fn unaligned_pixels_keep_their_neighbours() {
+    let (emulator, mut screen) =
+        Emulator::screen(ScreenBuilder::preset(Preset::CFAP200200A0_154)).unwrap();
+    let mut buf = vec![0x00; 5000];
+    screen.show_full_screen_image(&buf).unwrap();
+    let mut shadow = Shadow::new(&mut buf, 200, 200).unwrap();
+
+    // 10 by 2 white pixels at (3, 20)
+    screen.update_pixels(&mut shadow, 3, 20, 10, 2, &[0xff, 0xc0, 0xff, 0xc0]).unwrap();
+
+    for &(x, y) in [(3, 20), (12, 20), (3, 21), (12, 21)].iter() {
+        assert!(emulator.pixel(x, y), "({}, {})", x, y);
+    }
+    for &(x, y) in [(2, 20), (13, 20), (3, 19), (3, 22), (15, 21)].iter() {
+        assert!(!emulator.pixel(x, y), "({}, {})", x, y);
+    }
+    assert_eq!(emulator.image(), shadow.as_bytes());
+    assert!(emulator.errors().is_empty(), "{:?}", emulator.errors());
+}
+
+#[test]
+fn only_the_enclosing_bytes_are_sent() {
+    let (recorder, mut screen) =
+        Recorder::screen(ScreenBuilder::preset(Preset::CFAP200200A0_154)).unwrap();
+    let mut buf = vec![0xff; 5000];
+    let mut shadow = Shadow::new(&mut buf, 200, 200).unwrap();
+    recorder.clear();
+
+    screen.load_pixels(&mut shadow, 6, 0, 4, 3, &[0x00, 0x00, 0x00]).unwrap();
+
+    assert_eq!(
+        recorder.commands(),
+        vec![
+            (0x44, vec![0, 1]),
+            (0x45, vec![199, 0, 197, 0]),
+            (0x4e, vec![0]),
+            (0x4f, vec![199, 0]),
+            (0x24, vec![0xfc, 0x3f, 0xfc, 0x3f, 0xfc, 0x3f]),
+        ]
+    );
+}
+
+#[test]
+fn updated_pixels_are_written_again() {
+    let (recorder, mut screen) =
+        Recorder::screen(ScreenBuilder::preset(Preset::CFAP200200A0_154)).unwrap();
+    let mut buf = vec![0xff; 5000];
+    let mut shadow = Shadow::new(&mut buf, 200, 200).unwrap();
+    recorder.clear();
+
+    screen.update_pixels(&mut shadow, 6, 0, 4, 1, &[0x00]).unwrap();
+
+    let writes: Vec<_> = recorder.commands().into_iter().filter(|c| c.0 == 0x24).collect();
+    assert_eq!(writes, vec![(0x24, vec![0xfc, 0x3f]), (0x24, vec![0xfc, 0x3f])]);
+    assert_eq!(recorder.commands().last(), Some(&(0x24, vec![0xfc, 0x3f])));
+}
+
+#[test]
+fn pixels_are_checked() {
+    let (emulator, mut screen) =
+        Emulator::screen(ScreenBuilder::preset(Preset::CFAP200200A0_154)).unwrap();
+    let mut buf = vec![0xff; 5000];
+    let mut shadow = Shadow::new(&mut buf, 200, 200).unwrap();
+
+    match screen.load_pixels(&mut shadow, 195, 0, 8, 1, &[0x00]) {
+        Err(ScreenError::BoundsError) => {}
+        _ => panic!("expected a bounds error"),
+    }
+    match screen.load_pixels(&mut shadow, 0, 0, 9, 1, &[0x00]) {
+        Err(ScreenError::LengthError) => {}
+        _ => panic!("expected a length error"),
+    }
+    assert!(shadow.as_bytes().iter().all(|&b| b == 0xff));
+    assert!(emulator.errors().is_empty());
+
+    let mut small = vec![0xff; 25];
+    assert!(Shadow::new(&mut small, 200, 2).is_none());
+}
+
+#[test]
+fn window_follows_the_entry_mode() {
+    let modes = [
+        EntryMode::XDecrementYDecrement,
+        EntryMode::XIncrementYDecrement,
+        EntryMode::XDecrementYIncrement,
+        EntryMode::XIncrementYIncrement,
+    ];
+    for &mode in modes.iter() {
+        let builder = ScreenBuilder::preset(Preset::CFAP200200A0_154).entry_mode(mode);
+        let (emulator, mut screen) = Emulator::screen(builder).unwrap();
+        let mut buf = vec![0xff; 5000];
+        let mut shadow = Shadow::new(&mut buf, 200, 200).unwrap();
+
+        screen.load_pixels(&mut shadow, 6, 3, 4, 2, &[0x00, 0x00]).unwrap();
+
+        let x_increment = mode as u8 & 0b01 != 0;
+        let y_increment = mode as u8 & 0b10 != 0;
+        for y in 3..5 {
+            let ram_y = if y_increment { y } else { 199 - y };
+            let ram = |x: u8| emulator.ram_byte(if x_increment { x } else { 24 - x }, ram_y);
+            assert_eq!((ram(0), ram(1)), (0xfc, 0x3f), "{:?}, row {}", mode, y);
+        }
+        assert!(emulator.errors().is_empty(), "{:?}", emulator.errors());
+    }
+}
+
+#[test]
+fn shadow_is_unchanged_when_writing_fails() {
+    let recorder = Recorder::new();
+    let fail = Rc::new(Cell::new(false));
+    let spi = FailingSpi { spi: recorder.spi(), fail: fail.clone() };
+    let mut screen = ScreenBuilder::preset(Preset::CFAP200200A0_154)
+        .new_screen(
+            spi,
+            recorder.dc(),
+            recorder.cs(),
+            recorder.busy(),
+            recorder.reset(),
+            &mut recorder.delay(),
+        )
+        .unwrap();
+    let mut buf = vec![0xff; 5000];
+    let mut shadow = Shadow::new(&mut buf, 200, 200).unwrap();
+
+    fail.set(true);
+    match screen.load_pixels(&mut shadow, 6, 0, 4, 1, &[0x00]) {
+        Err(ScreenError::SpiError(())) => {}
+        _ => panic!("expected an SPI error"),
+    }
+    assert!(shadow.as_bytes().iter().all(|&b| b == 0xff));
+
+    fail.set(false);
+    screen.load_pixels(&mut shadow, 6, 0, 4, 1, &[0x00]).unwrap();
+    assert_eq!(shadow.as_bytes()[..2], [0xfc, 0x3f]);
+}
@@ -0,0 +1,35 @@
+extern crate cfap_screen;
+
+use cfap_screen::*;
+
+#[test]
+fn fixed_size_constants() {
+    assert_eq!(CFAP200200A0_154Size::WIDTH, 200);
+    assert_eq!(CFAP200200A0_154Size::HEIGHT, 200);
+    assert_eq!(CFAP200200A0_154Size::LEN, 5000);
+    assert_eq!(FixedSize::<13, 2, { image_len(13, 2) }>::LEN, 4);
+}
+
+#[test]
+fn dimensions() {
+    let size = FixedSize::<128, 296, { image_len(128, 296) }>::new();
+    assert_eq!((size.x_size(), size.y_size()), (128, 296));
+
+    let size = DynamicSize { x_size: 13, y_size: 2 };
+    assert_eq!((size.x_size(), size.y_size()), (13, 2));
+}
+
+#[test]
+fn sized_presets_match_their_builders() {
+    let builder = CFAP200200A0_154::builder();
+    assert_eq!(
+        (builder.x_size, builder.y_size),
+        (CFAP200200A0_154Size::WIDTH, CFAP200200A0_154Size::HEIGHT)
+    );
+    assert_eq!(builder.vcom, VCOM_CFAP200200A0_154);
+
+    let builder = CFAP200200A1_154::builder();
+    let size = CFAP200200A1_154::size();
+    assert_eq!((builder.x_size, builder.y_size), (size.x_size(), size.y_size()));
+    assert_eq!(builder.vcom, VCOM_CFAP200200A1_154);
+}
@@ -0,0 +1,94 @@
+extern crate cfap_screen;
+
+mod common;
+
+use cfap_screen::emulator::Emulator;
+use cfap_screen::*;
+
+use common::collect;
+
+#[test]
+fn generated_image_is_shown() {
+    let (emulator, mut screen) =
+        Emulator::screen(ScreenBuilder::preset(Preset::CFAP200200A0_154)).unwrap();
+
+    screen.show_full_screen_source(from_fn(5000, |i| (i % 25) as u8)).unwrap();
+
+    let expected: Vec<u8> = (0..5000).map(|i| (i % 25) as u8).collect();
+    assert_eq!(emulator.image(), expected);
+}
+
+#[test]
+fn rows_are_rendered_into_one_buffer() {
+    let (emulator, mut screen) =
+        Emulator::screen(ScreenBuilder::preset(Preset::CFAP200200A0_154)).unwrap();
+    let mut row = [0; 25];
+
+    screen
+        .show_full_screen_source(Rows::new(&mut row, 200, |y, buf| {
+            for b in buf.iter_mut() {
+                *b = y as u8;
+            }
+        }))
+        .unwrap();
+
+    let image = emulator.image();
+    assert_eq!(image[..25], [0; 25]);
+    assert_eq!(image[199 * 25..], [199; 25]);
+}
+
+#[test]
+fn full_screen_source_checks_length() {
+    let (emulator, mut screen) =
+        Emulator::screen(ScreenBuilder::preset(Preset::CFAP200200A0_154)).unwrap();
+
+    match screen.show_full_screen_source(from_fn(4999, |_| 0)) {
+        Err(ScreenError::LengthError) => {}
+        _ => panic!("expected a length error"),
+    }
+    assert_eq!(emulator.updates(), 0);
+}
+
+#[test]
+fn sub_image_of_a_larger_buffer() {
+    let data: Vec<u8> = (0..40).collect();
+
+    let sub = SubImage::new(&data, 8, 2, 1, 3, 2).unwrap();
+    assert_eq!(collect(sub), vec![10, 11, 12, 18, 19, 20]);
+
+    assert!(SubImage::new(&data, 8, 6, 0, 3, 1).is_none());
+    assert!(SubImage::new(&data, 8, 0, 4, 8, 2).is_none());
+    assert!(SubImage::new(&data, 8, 0, 4, 8, 1).is_some());
+}
+
+#[test]
+fn sub_image_rejects_overflowing_rectangles() {
+    let data = [0; 40];
+
+    assert!(SubImage::new(&data, 8, usize::MAX, 0, 1, 1).is_none());
+    assert!(SubImage::new(&data, 8, 0, usize::MAX, 1, 1).is_none());
+    assert!(SubImage::new(&data, usize::MAX, 1, 1, 1, 1).is_none());
+    assert!(SubImage::new(&data, 8, 0, 0, 1, usize::MAX).is_none());
+}
+
+#[test]
+fn partial_image_from_sub_image() {
+    let (emulator, mut screen) =
+        Emulator::screen(ScreenBuilder::preset(Preset::CFAP200200A0_154)).unwrap();
+    let data: Vec<u8> = (0..40).collect();
+
+    let sub = SubImage::new(&data, 8, 2, 1, 3, 2).unwrap();
+    screen.load_partial_image(4, 3, 0, 2, sub).unwrap();
+
+    assert_eq!(emulator.ram_byte(4, 0), 10);
+    assert_eq!(emulator.ram_byte(6, 1), 20);
+}
+
+#[test]
+fn sources_can_be_reused_by_reference() {
+    let mut image = from_fn(3, |i| i as u8);
+
+    assert_eq!(collect(&mut image), vec![0, 1, 2]);
+    assert_eq!(collect(&mut image), vec![0, 1, 2]);
+    assert_eq!(collect(&[7u8, 8][..]), vec![7, 8]);
+}
@@ -0,0 +1,53 @@
+extern crate cfap_screen;
+
+use cfap_screen::*;
+
+#[test]
+fn presets_round_trip() {
+    let luts = [
+        LUT_FULL_CFAP200200A0_154,
+        LUT_FULL_CFAP200200A1_154,
+        LUT_PART_CFAP200200A0_154,
+        LUT_PART_CFAP200200A1_154,
+    ];
+
+    for lut in luts.iter() {
+        assert_eq!(Waveform::decode(lut).unwrap().encode().unwrap(), *lut);
+    }
+}
+
+#[test]
+fn decodes_fields() {
+    let waveform = Waveform::decode(&LUT_PART_CFAP200200A0_154).unwrap();
+
+    // 0x18
+    assert_eq!(
+        waveform.phases[1],
+        Phase {
+            from_0_to_0: Voltage::Vss,
+            from_0_to_1: Voltage::Vsh,
+            from_1_to_0: Voltage::Vsl,
+            from_1_to_1: Voltage::Vss,
+            frames: 1,
+        }
+    );
+    // 0x13
+    assert_eq!(waveform.phases[0].frames, 3);
+    assert_eq!(waveform.total_frames(), 3 + 1 + 4 + 1 + 4 + 4 + 2 + 1);
+}
+
+#[test]
+fn rejects_reserved_voltage() {
+    let mut lut = LUT_FULL_CFAP200200A0_154;
+    lut[7] = 0b0011_0000;
+
+    assert_eq!(Waveform::decode(&lut), Err(WaveformError::ReservedVoltage(7)));
+}
+
+#[test]
+fn rejects_frame_overflow() {
+    let mut waveform = Waveform::default();
+    waveform.phases[4].frames = MAX_FRAMES + 1;
+
+    assert_eq!(waveform.encode(), Err(WaveformError::FramesOutOfRange(4)));
+}